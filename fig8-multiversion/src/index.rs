@@ -2,10 +2,11 @@
 // https://github.com/pubgrub-rs/advanced_dependency_providers/
 
 use core::ops::{Bound, RangeBounds};
+use itertools::Either;
 use pubgrub::range::Range;
 use pubgrub::type_aliases::Map;
 use pubgrub::version::SemanticVersion as SemVer;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet as Set};
 
 /// Each package is identified by its name.
 pub type PackageName = String;
@@ -16,6 +17,29 @@ pub type Deps = Map<PackageName, Range<SemVer>>;
 pub struct Index {
     /// Specify dependencies of each package version.
     pub packages: Map<PackageName, BTreeMap<SemVer, Deps>>,
+    /// Options tuning the resolution.
+    pub options: ResolveOptions,
+}
+
+/// Options tuning how the index is resolved.
+#[derive(Debug, Clone, Default)]
+pub struct ResolveOptions {
+    /// Order in which versions are tried by the resolution.
+    pub version_order: VersionOrder,
+}
+
+/// Order in which versions are tried by the resolution.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum VersionOrder {
+    /// Newest versions first.
+    #[default]
+    Newest,
+    /// Oldest versions first, to check that lower bounds of dependencies are correct.
+    Oldest,
+    /// Oldest versions first for the given packages, usually the direct dependencies
+    /// of the roots computed once by [Index::direct_dependencies],
+    /// and newest versions first for other packages.
+    OldestDirect(Set<PackageName>),
 }
 
 impl Index {
@@ -23,16 +47,39 @@ impl Index {
     pub fn new() -> Self {
         Self {
             packages: Map::default(),
+            options: ResolveOptions::default(),
         }
     }
 
-    /// List existing versions for a given package with newest versions first.
+    /// List existing versions for a given package in the order they should be tried,
+    /// newest versions first unless another version order is set.
     pub fn available_versions(&self, package: &PackageName) -> impl Iterator<Item = &SemVer> {
-        self.packages
-            .get(package)
-            .into_iter()
-            .flat_map(|k| k.keys())
-            .rev()
+        let versions = self.packages.get(package).into_iter().flat_map(|k| k.keys());
+        if self.oldest_first(package) {
+            Either::Left(versions)
+        } else {
+            Either::Right(versions.rev())
+        }
+    }
+
+    /// Whether the oldest versions of a package should be tried first.
+    fn oldest_first(&self, package: &PackageName) -> bool {
+        match &self.options.version_order {
+            VersionOrder::Newest => false,
+            VersionOrder::Oldest => true,
+            VersionOrder::OldestDirect(direct) => direct.contains(package),
+        }
+    }
+
+    /// Packages depended upon by any version of the roots,
+    /// to try their oldest versions first with [VersionOrder::OldestDirect].
+    pub fn direct_dependencies(&self, roots: &[&str]) -> Set<PackageName> {
+        roots
+            .iter()
+            .filter_map(|root| self.packages.get(*root))
+            .flat_map(|versions| versions.values())
+            .flat_map(|deps| deps.keys().cloned())
+            .collect()
     }
 
     /// Register a package and its dependencies in the index.
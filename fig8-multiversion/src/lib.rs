@@ -1,2 +1,3 @@
 pub mod index;
 pub mod multiple_versions;
+pub mod tree;
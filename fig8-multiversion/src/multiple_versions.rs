@@ -71,7 +71,7 @@ impl Display for Bucket {
 }
 
 impl Index {
-    /// List existing versions for a given package with newest versions first.
+    /// List existing versions for a given package in the index version order.
     pub fn list_versions(&self, package: &Package) -> impl Iterator<Item = SemVer> + '_ {
         match package {
             // If we are on a bucket, we need to filter versions
@@ -109,7 +109,8 @@ impl Index {
 /// So [1.1, 1.2, 2.3] -> [1.0, 2.0]
 fn bucket_versions(versions: impl Iterator<Item = SemVer>) -> impl Iterator<Item = SemVer> {
     let mut current_bucket = None;
-    // This filter_map makes the hypothesis that versions are sorted in a normal or reverse order.
+    // This filter_map makes the hypothesis that versions are sorted in a normal or reverse order,
+    // which holds for every version order of the index.
     // Would need a bit more work if they are not ordered due to prioritizations, etc.
     versions.filter_map(move |v| {
         let v_bucket = Some(bucket_version(v));
@@ -221,6 +222,7 @@ fn single_bucket_spanned(range: &Range<SemVer>) -> Option<u32> {
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::index::VersionOrder;
     use core::fmt::Debug;
     use pubgrub::error::PubGrubError;
     use pubgrub::type_aliases::{Map, SelectedDependencies};
@@ -338,4 +340,25 @@ pub mod tests {
         index.add_deps::<R>("d", (1, 5, 0), &[]);
         assert!(resolve(&index, "a#1", (1, 0, 0)).is_err());
     }
+
+    #[test]
+    /// With oldest versions first, the lowest bucket matching the range is selected.
+    fn success_when_oldest_versions_first() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(3, 0, 0))]);
+        index.add_deps("b", (1, 0, 0), &[("c", ..)]);
+        index.add_deps("b", (2, 0, 0), &[("c", ..)]);
+        index.add_deps::<R>("c", (1, 0, 0), &[]);
+        index.add_deps::<R>("c", (1, 2, 0), &[]);
+        index.options.version_order = VersionOrder::Oldest;
+        assert_map_eq(
+            &resolve(&index, "a#1", (1, 0, 0)).unwrap(),
+            &select(&[("a#1", (1, 0, 0)), ("b#1", (1, 0, 0)), ("c#1", (1, 0, 0))]),
+        );
+        index.options.version_order = VersionOrder::OldestDirect(index.direct_dependencies(&["a"]));
+        assert_map_eq(
+            &resolve(&index, "a#1", (1, 0, 0)).unwrap(),
+            &select(&[("a#1", (1, 0, 0)), ("b#1", (1, 0, 0)), ("c#1", (1, 2, 0))]),
+        );
+    }
 }
@@ -0,0 +1,241 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{Index, PackageName};
+use crate::multiple_versions::Package;
+use pubgrub::solver::{Dependencies, DependencyProvider};
+use pubgrub::type_aliases::SelectedDependencies;
+use pubgrub::version::SemanticVersion as SemVer;
+use std::collections::{BTreeMap as Map, BTreeSet as Set};
+use std::fmt::Write;
+
+/// A selected version of a package.
+type Node<'s> = (&'s PackageName, SemVer);
+
+/// Selected package versions of a solution, with the dependencies between them.
+type ResolvedGraph<'s> = Map<Node<'s>, Set<Node<'s>>>;
+
+impl Index {
+    /// Indented tree of a solution from the root, like `cargo tree`.
+    ///
+    /// Each line is a package version, like "d v1.0.0".
+    /// Dependencies are sorted, and a package version whose dependencies were already printed
+    /// is marked with "(*)" instead of being expanded again.
+    pub fn tree(&self, solution: &SelectedDependencies<Package, SemVer>, root: &Package) -> String {
+        let graph = self.resolved_graph(solution);
+        let mut tree = String::new();
+        if let (Package::Bucket(bucket), Some(version)) = (root, solution.get(root)) {
+            if let Some((root, _)) = graph.get_key_value(&(&bucket.name, *version)) {
+                write_tree(&graph, *root, &mut Set::new(), &mut tree);
+            }
+        }
+        tree
+    }
+
+    /// Inverted trees of a solution, showing the dependents of each selected version
+    /// of a package, like `cargo tree -i`.
+    pub fn inverted_tree(
+        &self,
+        solution: &SelectedDependencies<Package, SemVer>,
+        package: &str,
+    ) -> String {
+        let dependents = dependents(&self.resolved_graph(solution));
+        let mut trees = Vec::new();
+        for node in dependents.keys().filter(|(name, _)| *name == package) {
+            let mut tree = String::new();
+            write_tree(&dependents, *node, &mut Set::new(), &mut tree);
+            trees.push(tree);
+        }
+        trees.join("\n")
+    }
+
+    /// Inverted trees of every package selected in more than one version,
+    /// like `cargo tree --duplicates`.
+    pub fn duplicates_tree(&self, solution: &SelectedDependencies<Package, SemVer>) -> String {
+        let dependents = dependents(&self.resolved_graph(solution));
+        let mut names: Vec<&PackageName> = dependents.keys().map(|(name, _)| *name).collect();
+        names.dedup();
+        let mut trees = Vec::new();
+        for name in names {
+            let versions: Vec<_> = dependents.keys().filter(|(n, _)| *n == name).collect();
+            if versions.len() > 1 {
+                for node in versions {
+                    let mut tree = String::new();
+                    write_tree(&dependents, *node, &mut Set::new(), &mut tree);
+                    trees.push(tree);
+                }
+            }
+        }
+        trees.join("\n")
+    }
+
+    /// Dependencies between the selected buckets of a solution, seeing through proxies.
+    fn resolved_graph<'s>(
+        &self,
+        solution: &'s SelectedDependencies<Package, SemVer>,
+    ) -> ResolvedGraph<'s> {
+        let mut graph = ResolvedGraph::new();
+        for (package, version) in solution {
+            if let Package::Bucket(bucket) = package {
+                let dependencies = graph.entry((&bucket.name, *version)).or_default();
+                for (dep, dep_version) in self.selected_dependencies(solution, package, version) {
+                    // A proxy depends on a single bucket.
+                    let (dep, dep_version) = match dep {
+                        Package::Proxy { .. } => match self
+                            .selected_dependencies(solution, dep, dep_version)
+                            .first()
+                        {
+                            Some(selected) => *selected,
+                            None => continue,
+                        },
+                        Package::Bucket(_) => (dep, dep_version),
+                    };
+                    if let Package::Bucket(dep) = dep {
+                        dependencies.insert((&dep.name, *dep_version));
+                    }
+                }
+            }
+        }
+        graph
+    }
+
+    /// Dependencies of a selected package, restricted to the selected ones.
+    fn selected_dependencies<'s>(
+        &self,
+        solution: &'s SelectedDependencies<Package, SemVer>,
+        package: &Package,
+        version: &SemVer,
+    ) -> Vec<(&'s Package, &'s SemVer)> {
+        match self.get_dependencies(package, version) {
+            Ok(Dependencies::Known(deps)) => deps
+                .iter()
+                .filter_map(|(dep, range)| {
+                    solution
+                        .get_key_value(dep)
+                        .filter(|(_, v)| range.contains(v))
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// The dependencies of a resolved graph, inverted.
+fn dependents<'s>(graph: &ResolvedGraph<'s>) -> ResolvedGraph<'s> {
+    let mut dependents = ResolvedGraph::new();
+    for (node, dependencies) in graph {
+        dependents.entry(*node).or_default();
+        for dep in dependencies {
+            dependents.entry(*dep).or_default().insert(*node);
+        }
+    }
+    dependents
+}
+
+/// Write a node, and its children if it was not printed yet.
+fn write_tree<'s>(
+    children: &ResolvedGraph<'s>,
+    node: Node<'s>,
+    printed: &mut Set<Node<'s>>,
+    tree: &mut String,
+) {
+    if write_line(children, node, printed, tree) {
+        write_children(children, node, "", printed, tree);
+    }
+}
+
+fn write_children<'s>(
+    children: &ResolvedGraph<'s>,
+    node: Node<'s>,
+    prefix: &str,
+    printed: &mut Set<Node<'s>>,
+    tree: &mut String,
+) {
+    let count = children.get(&node).map_or(0, |c| c.len());
+    for (i, child) in children.get(&node).into_iter().flatten().enumerate() {
+        let last = i + 1 == count;
+        tree.push_str(prefix);
+        tree.push_str(if last { "└── " } else { "├── " });
+        if write_line(children, *child, printed, tree) {
+            let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            write_children(children, *child, &prefix, printed, tree);
+        }
+    }
+}
+
+/// Write the line of a node, returning whether its children should be written.
+fn write_line<'s>(
+    children: &ResolvedGraph<'s>,
+    node: Node<'s>,
+    printed: &mut Set<Node<'s>>,
+    tree: &mut String,
+) -> bool {
+    let (name, version) = node;
+    let _ = write!(tree, "{} v{}", name, version);
+    let expand = printed.insert(node);
+    if !expand && children.get(&node).is_some_and(|c| !c.is_empty()) {
+        tree.push_str(" (*)");
+    }
+    tree.push('\n');
+    expand
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    type R = core::ops::RangeFull;
+
+    #[test]
+    fn tree_inverted_and_duplicates() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0))]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0))]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0))]);
+        index.add_deps(
+            "c",
+            (1, 0, 0),
+            &[("d", (1, 0, 0)..(3, 0, 0)), ("b", (1, 0, 0)..(2, 0, 0))],
+        );
+        index.add_deps::<R>("d", (1, 0, 0), &[]);
+        index.add_deps::<R>("d", (2, 0, 0), &[]);
+        let root = Package::from_str("a#1").unwrap();
+        let solution = pubgrub::solver::resolve(&index, root.clone(), (1, 0, 0)).unwrap();
+        assert_eq!(
+            index.tree(&solution, &root),
+            "\
+a v1.0.0
+├── b v1.0.0
+│   └── d v1.0.0
+└── c v1.0.0
+    ├── b v1.0.0 (*)
+    └── d v2.0.0
+"
+        );
+        assert_eq!(
+            index.inverted_tree(&solution, "b"),
+            "\
+b v1.0.0
+├── a v1.0.0
+└── c v1.0.0
+    └── a v1.0.0
+"
+        );
+        assert_eq!(
+            index.duplicates_tree(&solution),
+            "\
+d v1.0.0
+└── b v1.0.0
+    ├── a v1.0.0
+    └── c v1.0.0
+        └── a v1.0.0
+
+d v2.0.0
+└── c v1.0.0
+    └── a v1.0.0
+"
+        );
+    }
+}
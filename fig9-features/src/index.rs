@@ -2,9 +2,10 @@
 // https://github.com/pubgrub-rs/advanced_dependency_providers/
 
 use core::ops::{Bound, RangeBounds};
+use itertools::Either;
 use pubgrub::range::Range;
 use pubgrub::type_aliases::Map;
-use pubgrub::version::NumberVersion as Version;
+use pubgrub::version::{NumberVersion, Version};
 use std::collections::{BTreeMap, BTreeSet as Set};
 
 /// Each package is identified by its name.
@@ -13,29 +14,56 @@ pub type PackageName = String;
 pub type Feature = String;
 
 /// Global registry of known packages.
-pub struct Index {
+/// Versions are plain numbers by default,
+/// but any ecosystem version type implementing pubgrub `Version` can be used.
+pub struct Index<V: Version = NumberVersion> {
     /// Specify dependencies of each package version.
-    pub packages: Map<PackageName, BTreeMap<Version, Deps>>,
+    pub packages: Map<PackageName, BTreeMap<V, Deps<V>>>,
+    /// Options tuning the resolution.
+    pub options: ResolveOptions,
+}
+
+/// Options tuning how the index is resolved.
+#[derive(Debug, Clone, Default)]
+pub struct ResolveOptions {
+    /// Order in which versions are tried by the resolution.
+    pub version_order: VersionOrder,
+}
+
+/// Order in which versions are tried by the resolution.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum VersionOrder {
+    /// Newest versions first.
+    #[default]
+    Newest,
+    /// Oldest versions first, to check that lower bounds of dependencies are correct.
+    Oldest,
+    /// Oldest versions first for the given packages, usually the direct dependencies
+    /// of the roots computed once by [Index::direct_dependencies],
+    /// and newest versions first for other packages.
+    OldestDirect(Set<PackageName>),
 }
 
 /// Dependencies include mandatory dependencies and optional dependencies.
 /// Optional dependencies are identified by an option called a "feature".
-pub struct Deps {
+pub struct Deps<V: Version = NumberVersion> {
     /// The regular, mandatory dependencies.
-    pub mandatory: Map<PackageName, Dep>,
+    pub mandatory: Map<PackageName, Dep<V>>,
     /// The optional, feature-gated dependencies.
-    pub optional: Map<Feature, Map<PackageName, Dep>>,
+    pub optional: Map<Feature, Map<PackageName, Dep<V>>>,
 }
 
 /// A dependency is specified with a range, and with a set of activated features.
-pub struct Dep {
+pub struct Dep<V: Version = NumberVersion> {
     /// The range dependended upon.
-    pub range: Range<Version>,
+    pub range: Range<V>,
     /// The activated features for that dependency.
     pub features: Set<Feature>,
+    /// Whether pre-releases may be picked for that dependency, like when they are requested.
+    pub prereleases: bool,
 }
 
-impl Default for Deps {
+impl<V: Version> Default for Deps<V> {
     fn default() -> Self {
         Self {
             mandatory: Map::default(),
@@ -44,21 +72,30 @@ impl Default for Deps {
     }
 }
 
-impl Index {
-    /// Empty new index.
-    pub fn new() -> Self {
+impl<V: Version> Default for Index<V> {
+    fn default() -> Self {
         Self {
             packages: Map::default(),
+            options: ResolveOptions::default(),
         }
     }
+}
 
-    /// List existing versions for a given package with newest versions first.
-    pub fn available_versions(&self, package: &PackageName) -> impl Iterator<Item = &Version> {
-        self.packages
-            .get(package)
-            .into_iter()
-            .flat_map(|k| k.keys())
-            .rev()
+/// Versions which can be pre-releases.
+/// Pre-releases are only picked for dependencies requesting them,
+/// or when nothing else matches the dependency.
+pub trait Prerelease {
+    fn is_prerelease(&self) -> bool {
+        false
+    }
+}
+
+impl Prerelease for NumberVersion {}
+
+impl Index {
+    /// Empty new index.
+    pub fn new() -> Self {
+        Self::default()
     }
 
     /// Register a package and its mandatory dependencies in the index.
@@ -68,19 +105,11 @@ impl Index {
         version: u32,
         mandatory_deps: &[(&str, R, &[&str])],
     ) {
-        let deps = self
-            .packages
-            .entry(package.to_string())
-            .or_default()
-            .entry(version.into())
-            .or_default();
-        for (p, r, features) in mandatory_deps {
-            let dep = Dep {
-                range: range_from_bounds(r),
-                features: features.iter().map(|s| s.to_string()).collect(),
-            };
-            deps.mandatory.insert(String::from(*p), dep);
-        }
+        let deps: Vec<_> = mandatory_deps
+            .iter()
+            .map(|(p, r, features)| (*p, range_from_bounds(r), *features))
+            .collect();
+        self.add_dependencies(package, version, &deps);
     }
 
     /// Register a feature and its associated dependencies for a given package in the index.
@@ -91,27 +120,113 @@ impl Index {
         feature: &str,
         optional_deps: &[(&str, R, &[&str])],
     ) {
+        let deps: Vec<_> = optional_deps
+            .iter()
+            .map(|(p, r, features)| (*p, range_from_bounds(r), *features))
+            .collect();
+        self.add_feature_dependencies(package, version, feature, &deps);
+    }
+}
+
+impl<V: Version> Index<V> {
+    /// List existing versions for a given package in the order they should be tried,
+    /// newest versions first unless another version order is set.
+    pub fn available_versions(&self, package: &PackageName) -> impl Iterator<Item = &V> {
+        let versions = self.packages.get(package).into_iter().flat_map(|k| k.keys());
+        if self.oldest_first(package) {
+            Either::Left(versions)
+        } else {
+            Either::Right(versions.rev())
+        }
+    }
+
+    /// Whether the oldest versions of a package should be tried first.
+    fn oldest_first(&self, package: &PackageName) -> bool {
+        match &self.options.version_order {
+            VersionOrder::Newest => false,
+            VersionOrder::Oldest => true,
+            VersionOrder::OldestDirect(direct) => direct.contains(package),
+        }
+    }
+
+    /// Packages depended upon by any version of the roots, mandatory or optional,
+    /// to try their oldest versions first with [VersionOrder::OldestDirect].
+    pub fn direct_dependencies(&self, roots: &[&str]) -> Set<PackageName> {
+        roots
+            .iter()
+            .filter_map(|root| self.packages.get(*root))
+            .flat_map(|versions| versions.values())
+            .flat_map(|deps| {
+                deps.mandatory
+                    .keys()
+                    .chain(deps.optional.values().flat_map(|d| d.keys()))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Register a package and its mandatory dependencies, given as pubgrub ranges, in the index.
+    pub fn add_dependencies(
+        &mut self,
+        package: &str,
+        version: impl Into<V>,
+        mandatory_deps: &[(&str, Range<V>, &[&str])],
+    ) {
+        let deps = self.deps_mut(package, version.into(), None);
+        for (p, r, features) in mandatory_deps {
+            deps.insert(String::from(*p), Dep::new(r.clone(), features));
+        }
+    }
+
+    /// Register a feature and its associated dependencies, given as pubgrub ranges,
+    /// for a given package in the index.
+    pub fn add_feature_dependencies(
+        &mut self,
+        package: &str,
+        version: impl Into<V>,
+        feature: &str,
+        optional_deps: &[(&str, Range<V>, &[&str])],
+    ) {
+        let deps = self.deps_mut(package, version.into(), Some(feature));
+        for (p, r, features) in optional_deps {
+            deps.insert(String::from(*p), Dep::new(r.clone(), features));
+        }
+    }
+
+    /// Mandatory dependencies of a package version, or those of one of its features,
+    /// registered if new.
+    pub(crate) fn deps_mut(
+        &mut self,
+        package: &str,
+        version: V,
+        feature: Option<&str>,
+    ) -> &mut Map<PackageName, Dep<V>> {
         let deps = self
             .packages
             .entry(package.to_string())
             .or_default()
-            .entry(version.into())
-            .or_default()
-            .optional
-            .entry(feature.to_string())
+            .entry(version)
             .or_default();
-        for (p, r, features) in optional_deps {
-            let dep = Dep {
-                range: range_from_bounds(r),
-                features: features.iter().map(|s| s.to_string()).collect(),
-            };
-            deps.insert(String::from(*p), dep);
+        match feature {
+            None => &mut deps.mandatory,
+            Some(feature) => deps.optional.entry(feature.to_string()).or_default(),
+        }
+    }
+}
+
+impl<V: Version> Dep<V> {
+    /// Dependency on a range with activated features, without pre-releases.
+    pub fn new(range: Range<V>, features: &[&str]) -> Self {
+        Self {
+            range,
+            features: features.iter().map(|s| s.to_string()).collect(),
+            prereleases: false,
         }
     }
 }
 
 /// Convert a range bounds into pubgrub Range type.
-fn range_from_bounds<R: RangeBounds<u32>>(bounds: &R) -> Range<Version> {
+fn range_from_bounds<R: RangeBounds<u32>>(bounds: &R) -> Range<NumberVersion> {
     match (bounds.start_bound(), bounds.end_bound()) {
         (Bound::Unbounded, Bound::Unbounded) => Range::any(),
         (Bound::Unbounded, Bound::Excluded(end)) => Range::strictly_lower_than(*end),
@@ -1,2 +1,4 @@
 pub mod index;
+pub mod opam_version;
 pub mod optional_deps;
+pub mod pep440;
@@ -0,0 +1,284 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::Prerelease;
+use core::cmp::Ordering;
+use core::fmt::Display;
+use pubgrub::range::Range;
+use pubgrub::version::Version;
+use std::str::FromStr;
+
+/// An opam package version.
+///
+/// Versions are compared like Debian versions: they are split in alternating
+/// non-digit and digit segments, digit segments are compared numerically,
+/// and in non-digit segments `~` sorts before anything, even the end of the segment,
+/// while letters sort before all other characters.
+/// So "1.0~beta" < "1.0" < "1.0a" < "1.0+flambda" < "1.0.1".
+#[derive(Debug, Clone)]
+pub struct OpamVersion {
+    /// None for the lowest version, which sorts before every real version.
+    version: Option<String>,
+    /// Number of times this version was bumped.
+    /// A bumped version is not a real version, it sits right after the original version
+    /// and before any other real version, which makes it usable as an exclusive bound in a `Range`.
+    /// There is no other way to get a successor since "1.0" < "1.0a~~" < "1.0a~" < "1.0a".
+    bumps: u32,
+}
+
+impl OpamVersion {
+    /// The version string, None for the lowest version.
+    pub fn as_str(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+}
+
+impl FromStr for OpamVersion {
+    type Err = String;
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let valid_char = |c: char| c.is_ascii_alphanumeric() || "-+.~_".contains(c);
+        if version.is_empty() || !version.chars().all(valid_char) {
+            return Err(format!("{} is not a valid opam version", version));
+        }
+        Ok(Self {
+            version: Some(version.to_string()),
+            bumps: 0,
+        })
+    }
+}
+
+impl Display for OpamVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.version {
+            None => write!(f, "⊥")?,
+            Some(version) => write!(f, "{}", version)?,
+        }
+        for _ in 0..self.bumps {
+            write!(f, "⁺")?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for OpamVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let versions_order = match (&self.version, &other.version) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(v1), Some(v2)) => compare_versions(v1, v2),
+        };
+        versions_order.then(self.bumps.cmp(&other.bumps))
+    }
+}
+
+impl PartialOrd for OpamVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Equality follows the ordering, so "1.0" and "1.00" are equal.
+impl PartialEq for OpamVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpamVersion {}
+
+impl Version for OpamVersion {
+    fn lowest() -> Self {
+        Self {
+            version: None,
+            bumps: 0,
+        }
+    }
+    fn bump(&self) -> Self {
+        Self {
+            version: self.version.clone(),
+            bumps: self.bumps + 1,
+        }
+    }
+}
+
+/// opam has no notion of pre-releases, "~" versions are simply ordered first.
+impl Prerelease for OpamVersion {}
+
+/// Range of versions matching an opam version constraint, like `>= "1.0"`.
+pub fn opam_range(op: &str, version: OpamVersion) -> Result<Range<OpamVersion>, String> {
+    match op {
+        "=" => Ok(Range::exact(version)),
+        "!=" => Ok(Range::exact(version).negate()),
+        "<" => Ok(Range::strictly_lower_than(version)),
+        "<=" => Ok(Range::strictly_lower_than(version.bump())),
+        ">" => Ok(Range::higher_than(version.bump())),
+        ">=" => Ok(Range::higher_than(version)),
+        _ => Err(format!("{} is not a valid opam version operator", op)),
+    }
+}
+
+/// Compare two version strings by alternating non-digit and digit segments.
+fn compare_versions(v1: &str, v2: &str) -> Ordering {
+    let (mut v1, mut v2) = (v1.as_bytes(), v2.as_bytes());
+    while !v1.is_empty() || !v2.is_empty() {
+        let (non_digits1, rest1) = split_segment(v1, |c| !c.is_ascii_digit());
+        let (non_digits2, rest2) = split_segment(v2, |c| !c.is_ascii_digit());
+        let (digits1, rest1) = split_segment(rest1, |c| c.is_ascii_digit());
+        let (digits2, rest2) = split_segment(rest2, |c| c.is_ascii_digit());
+        let order = compare_non_digits(non_digits1, non_digits2)
+            .then_with(|| compare_digits(digits1, digits2));
+        if order != Ordering::Equal {
+            return order;
+        }
+        v1 = rest1;
+        v2 = rest2;
+    }
+    Ordering::Equal
+}
+
+/// Split the longest prefix made of characters satisfying the predicate.
+fn split_segment(v: &[u8], predicate: impl Fn(u8) -> bool) -> (&[u8], &[u8]) {
+    let end = v.iter().position(|c| !predicate(*c)).unwrap_or(v.len());
+    v.split_at(end)
+}
+
+/// "~" sorts before the end of the segment, which sorts before letters,
+/// which sort before all other characters.
+fn char_weight(c: Option<u8>) -> i32 {
+    match c {
+        Some(b'~') => -1,
+        None => 0,
+        Some(c) if c.is_ascii_alphabetic() => c as i32,
+        Some(c) => c as i32 + 256,
+    }
+}
+
+fn compare_non_digits(s1: &[u8], s2: &[u8]) -> Ordering {
+    (0..s1.len().max(s2.len()))
+        .map(|i| char_weight(s1.get(i).copied()).cmp(&char_weight(s2.get(i).copied())))
+        .find(|order| order != &Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Compare digit segments numerically, without overflowing on long segments.
+fn compare_digits(s1: &[u8], s2: &[u8]) -> Ordering {
+    let trim = |s: &[u8]| {
+        let start = s.iter().position(|c| *c != b'0').unwrap_or(s.len());
+        s[start..].to_vec()
+    };
+    let (s1, s2) = (trim(s1), trim(s2));
+    s1.len().cmp(&s2.len()).then_with(|| s1.cmp(&s2))
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::index::Index;
+    use crate::optional_deps::Package;
+
+    fn v(version: &str) -> OpamVersion {
+        OpamVersion::from_str(version).unwrap()
+    }
+
+    #[test]
+    /// Orderings from the opam manual and the OpamVersionCompare tests.
+    fn known_orderings() {
+        let ordered = [
+            "~~",
+            "~~a",
+            "~",
+            "0",
+            "0.9",
+            "1.0~~",
+            "1.0~~a",
+            "1.0~",
+            "1.0~beta1",
+            "1.0~beta2",
+            "1.0~rc1",
+            "1.0",
+            "1.0a",
+            "1.0b",
+            "1.0+flambda",
+            "1.0-1",
+            "1.0.1",
+            "1.0.1+flambda",
+            "1.0.2",
+            "1.0.10",
+            "1.1",
+            "1.1+beta",
+            "2",
+            "v1.0",
+        ];
+        for window in ordered.windows(2) {
+            assert!(v(window[0]) < v(window[1]), "{} < {}", window[0], window[1]);
+        }
+    }
+
+    #[test]
+    fn leading_zeros_are_equal() {
+        assert_eq!(v("1.0"), v("1.00"));
+        assert_eq!(v("01.002"), v("1.2"));
+        assert_eq!(
+            v("1.123456789012345678901234567890"),
+            v("1.0123456789012345678901234567890")
+        );
+    }
+
+    #[test]
+    fn lowest_is_below_tildes() {
+        assert!(OpamVersion::lowest() < v("~~~~"));
+    }
+
+    #[test]
+    fn invalid_versions() {
+        assert!(OpamVersion::from_str("").is_err());
+        assert!(OpamVersion::from_str("1.0 beta").is_err());
+    }
+
+    #[test]
+    fn bump_is_the_successor() {
+        let bumped = v("1.0").bump();
+        assert!(v("1.0") < bumped);
+        for bigger in ["1.0a~~", "1.0+", "1.0.0", "1.0-0"] {
+            assert!(bumped < v(bigger), "{}⁺ < {}", "1.0", bigger);
+        }
+    }
+
+    #[test]
+    fn constraint_ranges() {
+        let at_most = opam_range("<=", v("1.0")).unwrap();
+        assert!(at_most.contains(&v("1.0~rc1")));
+        assert!(at_most.contains(&v("1.00")));
+        assert!(!at_most.contains(&v("1.0a")));
+        let above = opam_range(">", v("1.0")).unwrap();
+        assert!(!above.contains(&v("1.0")));
+        assert!(above.contains(&v("1.0.1")));
+        let not = opam_range("!=", v("1.0")).unwrap();
+        assert!(!not.contains(&v("1.0")));
+        assert!(not.contains(&v("1.0~")));
+        assert!(opam_range("~=", v("1.0")).is_err());
+    }
+
+    #[test]
+    /// The tilde release is picked over the final release only if the final release is excluded.
+    fn resolution_with_opam_versions() {
+        let mut index: Index<OpamVersion> = Index::default();
+        let below_final = opam_range("<", v("2.0")).unwrap();
+        let beta_or_more = opam_range(">=", v("2.0~beta")).unwrap();
+        index.add_dependencies(
+            "a",
+            v("1.0"),
+            &[("b", below_final.intersection(&beta_or_more), &[])],
+        );
+        index.add_dependencies("b", v("2.0~beta"), &[]);
+        index.add_dependencies("b", v("2.0"), &[]);
+        let solution =
+            pubgrub::solver::resolve(&index, Package::Base("a".into()), v("1.0")).unwrap();
+        assert_eq!(
+            solution.get(&Package::Base("b".into())),
+            Some(&v("2.0~beta"))
+        );
+    }
+}
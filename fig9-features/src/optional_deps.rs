@@ -1,13 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 // https://github.com/pubgrub-rs/advanced_dependency_providers/
 
-use crate::index::{Dep, Index};
+use crate::index::{Dep, Index, PackageName, Prerelease};
 use core::borrow::Borrow;
 use core::fmt::Display;
 use pubgrub::range::Range;
 use pubgrub::solver::{Dependencies, DependencyConstraints, DependencyProvider};
 use pubgrub::type_aliases::Map;
-use pubgrub::version::NumberVersion as Version;
+use pubgrub::version::Version;
 use std::str::FromStr;
 
 /// A package is either a base package like "a",
@@ -51,18 +51,18 @@ impl Display for Package {
     }
 }
 
-impl Index {
-    /// List existing versions for a given package with newest versions first.
-    pub fn list_versions(&self, package: &Package) -> impl Iterator<Item = &Version> {
+impl<V: Version + Prerelease> Index<V> {
+    /// List existing versions for a given package in the index version order.
+    pub fn list_versions(&self, package: &Package) -> impl Iterator<Item = &V> {
         self.available_versions(package.base_pkg())
     }
 }
 
-impl DependencyProvider<Package, Version> for Index {
-    fn choose_package_version<T: Borrow<Package>, U: Borrow<Range<Version>>>(
+impl<V: Version + Prerelease> DependencyProvider<Package, V> for Index<V> {
+    fn choose_package_version<T: Borrow<Package>, U: Borrow<Range<V>>>(
         &self,
         potential_packages: impl Iterator<Item = (T, U)>,
-    ) -> Result<(T, Option<Version>), Box<dyn std::error::Error>> {
+    ) -> Result<(T, Option<V>), Box<dyn std::error::Error>> {
         Ok(pubgrub::solver::choose_package_with_fewest_versions(
             |p| self.list_versions(p).cloned(),
             potential_packages,
@@ -72,8 +72,8 @@ impl DependencyProvider<Package, Version> for Index {
     fn get_dependencies(
         &self,
         package: &Package,
-        version: &Version,
-    ) -> Result<Dependencies<Package, Version>, Box<dyn std::error::Error>> {
+        version: &V,
+    ) -> Result<Dependencies<Package, V>, Box<dyn std::error::Error>> {
         let all_versions = match self.packages.get(package.base_pkg()) {
             None => return Ok(Dependencies::Unknown),
             Some(all_versions) => all_versions,
@@ -85,12 +85,14 @@ impl DependencyProvider<Package, Version> for Index {
 
         match package {
             // If we asked for a base package, we simply return the mandatory dependencies.
-            Package::Base(_) => Ok(Dependencies::Known(from_deps(&deps.mandatory))),
+            Package::Base(_) => Ok(Dependencies::Known(
+                self.dependency_constraints(&deps.mandatory),
+            )),
             // Otherwise, we concatenate the feature deps with a dependency to the base package.
             Package::Feature { base, feature } => match deps.optional.get(feature) {
                 None => Ok(Dependencies::Unknown),
                 Some(feature_deps) => {
-                    let mut all_deps = from_deps(feature_deps);
+                    let mut all_deps = self.dependency_constraints(feature_deps);
                     all_deps.insert(
                         Package::Base(base.to_string()),
                         Range::exact(version.clone()),
@@ -102,30 +104,52 @@ impl DependencyProvider<Package, Version> for Index {
     }
 }
 
-/// Helper function to convert Index deps into what is expected by the dependency provider.
-fn from_deps(deps: &Map<String, Dep>) -> DependencyConstraints<Package, Version> {
-    deps.iter()
-        .flat_map(|(base_pkg, dep)| {
-            let feature_count = dep.features.len();
-            dep.features
-                .iter()
-                .map(move |feat| {
-                    (
-                        Package::Feature {
-                            base: base_pkg.clone(),
-                            feature: feat.clone(),
-                        },
-                        dep.range.clone(),
-                    )
-                })
-                .chain(std::iter::once((
-                    Package::Base(base_pkg.clone()),
-                    dep.range.clone(),
-                )))
-                // If there was no feature, we take the base package, otherwise, we don't.
-                .take(feature_count.max(1))
+impl<V: Version + Prerelease> Index<V> {
+    /// Helper function to convert Index deps into what is expected by the dependency provider.
+    fn dependency_constraints(
+        &self,
+        deps: &Map<String, Dep<V>>,
+    ) -> DependencyConstraints<Package, V> {
+        deps.iter()
+            .flat_map(|(base_pkg, dep)| {
+                let feature_count = dep.features.len();
+                let range = self.dependency_range(base_pkg, dep);
+                let base = (Package::Base(base_pkg.clone()), range.clone());
+                dep.features
+                    .iter()
+                    .map(move |feat| {
+                        (
+                            Package::Feature {
+                                base: base_pkg.clone(),
+                                feature: feat.clone(),
+                            },
+                            range.clone(),
+                        )
+                    })
+                    .chain(std::iter::once(base))
+                    // If there was no feature, we take the base package, otherwise, we don't.
+                    .take(feature_count.max(1))
+            })
+            .collect()
+    }
+
+    /// Range of a dependency, without the pre-releases of the package
+    /// unless the dependency requests them, or nothing else matches it.
+    fn dependency_range(&self, package: &PackageName, dep: &Dep<V>) -> Range<V> {
+        if dep.prereleases {
+            return dep.range.clone();
+        }
+        let (prereleases, releases): (Vec<&V>, Vec<&V>) = self
+            .available_versions(package)
+            .filter(|v| dep.range.contains(v))
+            .partition(|v| v.is_prerelease());
+        if releases.is_empty() {
+            return dep.range.clone();
+        }
+        prereleases.into_iter().fold(dep.range.clone(), |range, v| {
+            range.intersection(&Range::exact(v.clone()).negate())
         })
-        .collect()
+    }
 }
 
 // TESTS #######################################################################
@@ -133,9 +157,11 @@ fn from_deps(deps: &Map<String, Dep>) -> DependencyConstraints<Package, Version>
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::index::VersionOrder;
     use core::fmt::Debug;
     use pubgrub::error::PubGrubError;
     use pubgrub::type_aliases::{Map, SelectedDependencies};
+    use pubgrub::version::NumberVersion as Version;
     type R = core::ops::RangeFull;
 
     /// Helper function to simplify the tests code.
@@ -277,4 +303,20 @@ pub mod tests {
         index.add_deps::<R>("f2", 0, &[]);
         assert!(resolve(&index, "a", 0).is_err());
     }
+
+    #[test]
+    fn oldest_versions_first() {
+        let mut index = Index::new();
+        index.add_deps("a", 0, &[("b", .., &[])]);
+        index.add_deps("b", 1, &[("c", .., &[])]);
+        index.add_deps("b", 2, &[("c", .., &[])]);
+        index.add_deps::<R>("c", 1, &[]);
+        index.add_deps::<R>("c", 2, &[]);
+        index.options.version_order = VersionOrder::Oldest;
+        let expected = select(&[("a", 0), ("b", 1), ("c", 1)]);
+        assert_map_eq(&resolve(&index, "a", 0).unwrap(), &expected);
+        index.options.version_order = VersionOrder::OldestDirect(index.direct_dependencies(&["a"]));
+        let expected = select(&[("a", 0), ("b", 1), ("c", 2)]);
+        assert_map_eq(&resolve(&index, "a", 0).unwrap(), &expected);
+    }
 }
@@ -0,0 +1,690 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{Dep, Index, Prerelease};
+use core::cmp::Ordering;
+use core::fmt::Display;
+use pubgrub::range::Range;
+use pubgrub::version::Version;
+use std::str::FromStr;
+
+/// A Python package version, following PEP 440.
+///
+/// Versions are ordered by epoch, release (ignoring trailing zeros), pre-release,
+/// post-release, dev-release and finally local version.
+/// So "1.0.dev1" < "1.0a1" < "1.0rc1" < "1.0" < "1.0+local" < "1.0.post1" < "1!0.1".
+#[derive(Debug, Clone)]
+pub struct Pep440Version {
+    epoch: u32,
+    release: Vec<u32>,
+    pre: Option<(PreRelease, u32)>,
+    post: Option<u32>,
+    dev: Option<u32>,
+    local: Vec<LocalSegment>,
+    /// Bounds used by specifiers which are not real versions.
+    marker: Marker,
+    /// Number of times this version was bumped.
+    /// A bumped version sits right after the original version and before any other version.
+    bumps: u32,
+}
+
+/// The kind of a pre-release, "a", "b" or "rc".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PreRelease {
+    Alpha,
+    Beta,
+    Rc,
+}
+
+/// Local version segments, numbers sort after strings.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LocalSegment {
+    Str(String),
+    Num(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Marker {
+    Real,
+    /// After this version and all its local versions.
+    AfterLocals,
+    /// After this version and all its post-releases.
+    AfterPosts,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum PreKey {
+    /// A dev-release without pre-release or post-release, like "1.0.dev1", comes before "1.0a1".
+    DevOfFinal,
+    Pre(PreRelease, u32),
+    Final,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum PostKey {
+    NoPost,
+    Post(u32),
+    AfterPosts,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum DevKey {
+    Dev(u32),
+    NoDev,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum LocalKey<'a> {
+    NoLocal,
+    Local(&'a [LocalSegment]),
+    AfterLocals,
+}
+
+type Key<'a> = (u32, &'a [u32], PreKey, PostKey, DevKey, LocalKey<'a>, u32);
+
+impl Pep440Version {
+    /// Whether this is a post-release, like "1.0.post1".
+    pub fn is_postrelease(&self) -> bool {
+        self.post.is_some()
+    }
+
+    fn key(&self) -> Key<'_> {
+        let trailing_zeros = self.release.iter().rev().take_while(|n| **n == 0).count();
+        let release = &self.release[..self.release.len() - trailing_zeros];
+        let pre = match (self.pre, self.post, self.dev) {
+            (Some((kind, n)), _, _) => PreKey::Pre(kind, n),
+            (None, None, Some(_)) => PreKey::DevOfFinal,
+            (None, _, _) => PreKey::Final,
+        };
+        let post = match (self.marker, self.post) {
+            (Marker::AfterPosts, _) => PostKey::AfterPosts,
+            (_, None) => PostKey::NoPost,
+            (_, Some(n)) => PostKey::Post(n),
+        };
+        let dev = match self.dev {
+            Some(n) => DevKey::Dev(n),
+            None => DevKey::NoDev,
+        };
+        let local = match self.marker {
+            Marker::AfterLocals => LocalKey::AfterLocals,
+            _ if self.local.is_empty() => LocalKey::NoLocal,
+            _ => LocalKey::Local(&self.local),
+        };
+        (self.epoch, release, pre, post, dev, local, self.bumps)
+    }
+
+    /// The first version of this release, like "1.0.dev0" for "1.0.post1".
+    fn release_start(&self) -> Self {
+        Self {
+            epoch: self.epoch,
+            release: self.release.clone(),
+            pre: None,
+            post: None,
+            dev: Some(0),
+            local: Vec::new(),
+            marker: Marker::Real,
+            bumps: 0,
+        }
+    }
+
+    /// The first version of the next release with the same number of components,
+    /// like "1.5.dev0" for "1.4".
+    fn next_release_start(&self) -> Self {
+        let mut next = self.release_start();
+        if let Some(last) = next.release.last_mut() {
+            *last += 1;
+        }
+        next
+    }
+
+    fn with_marker(&self, marker: Marker) -> Self {
+        Self {
+            local: Vec::new(),
+            marker,
+            bumps: 0,
+            ..self.clone()
+        }
+    }
+}
+
+impl Ord for Pep440Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl PartialOrd for Pep440Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Equality follows the ordering, so "1.0" and "1.0.0" are equal.
+impl PartialEq for Pep440Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pep440Version {}
+
+impl Version for Pep440Version {
+    fn lowest() -> Self {
+        Self::from_str("0.dev0").unwrap()
+    }
+    fn bump(&self) -> Self {
+        Self {
+            bumps: self.bumps + 1,
+            ..self.clone()
+        }
+    }
+}
+
+impl Prerelease for Pep440Version {
+    fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+}
+
+impl Display for Pep440Version {
+    /// Display the normalized form of the version.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}!", self.epoch)?;
+        }
+        let release: Vec<_> = self.release.iter().map(|n| n.to_string()).collect();
+        write!(f, "{}", release.join("."))?;
+        if let Some((kind, n)) = self.pre {
+            let kind = match kind {
+                PreRelease::Alpha => "a",
+                PreRelease::Beta => "b",
+                PreRelease::Rc => "rc",
+            };
+            write!(f, "{}{}", kind, n)?;
+        }
+        match (self.marker, self.post) {
+            (Marker::AfterPosts, _) => write!(f, ".post*")?,
+            (_, Some(n)) => write!(f, ".post{}", n)?,
+            (_, None) => {}
+        }
+        if let Some(n) = self.dev {
+            write!(f, ".dev{}", n)?;
+        }
+        if self.marker == Marker::AfterLocals {
+            write!(f, "+*")?;
+        } else if !self.local.is_empty() {
+            let local: Vec<_> = self
+                .local
+                .iter()
+                .map(|segment| match segment {
+                    LocalSegment::Str(s) => s.clone(),
+                    LocalSegment::Num(n) => n.to_string(),
+                })
+                .collect();
+            write!(f, "+{}", local.join("."))?;
+        }
+        for _ in 0..self.bumps {
+            write!(f, "⁺")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Pep440Version {
+    type Err = String;
+    /// Parse a version, accepting the alternative spellings normalized by PEP 440,
+    /// like "v1.0-Alpha.1" for "1.0a1" or "1.0-1" for "1.0.post1".
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{} is not a valid PEP 440 version", version);
+        let lowercase = version.trim().to_ascii_lowercase();
+        let normalized = lowercase.strip_prefix('v').unwrap_or(&lowercase);
+        let (public, local) = match normalized.split_once('+') {
+            Some((public, local)) => (public, Some(local)),
+            None => (normalized, None),
+        };
+        let (epoch, public) = match public.split_once('!') {
+            Some((epoch, public)) => (epoch.parse().map_err(|_| invalid())?, public),
+            None => (0, public),
+        };
+
+        let mut cursor = Cursor { rest: public };
+        let mut release = vec![cursor.number().ok_or_else(invalid)?];
+        while let Some(n) = cursor.attempt(|c| {
+            c.literal(".")?;
+            c.number()
+        }) {
+            release.push(n);
+        }
+        let pre = cursor.attempt(|c| {
+            c.separator();
+            let kind = match c.word(&["alpha", "a", "beta", "b", "preview", "pre", "rc", "c"])? {
+                "alpha" | "a" => PreRelease::Alpha,
+                "beta" | "b" => PreRelease::Beta,
+                _ => PreRelease::Rc,
+            };
+            c.separator();
+            Some((kind, c.number().unwrap_or(0)))
+        });
+        let post = cursor
+            .attempt(|c| {
+                c.literal("-")?;
+                c.number()
+            })
+            .or_else(|| {
+                cursor.attempt(|c| {
+                    c.separator();
+                    c.word(&["post", "rev", "r"])?;
+                    c.separator();
+                    Some(c.number().unwrap_or(0))
+                })
+            });
+        let dev = cursor.attempt(|c| {
+            c.separator();
+            c.word(&["dev"])?;
+            c.separator();
+            Some(c.number().unwrap_or(0))
+        });
+        if !cursor.rest.is_empty() {
+            return Err(invalid());
+        }
+
+        let local = match local {
+            None => Vec::new(),
+            Some(local) => local
+                .split(['.', '-', '_'])
+                .map(|segment| {
+                    if segment.is_empty() || !segment.chars().all(|c| c.is_ascii_alphanumeric()) {
+                        Err(invalid())
+                    } else if let Ok(n) = segment.parse() {
+                        Ok(LocalSegment::Num(n))
+                    } else {
+                        Ok(LocalSegment::Str(segment.to_string()))
+                    }
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        Ok(Self {
+            epoch,
+            release,
+            pre,
+            post,
+            dev,
+            local,
+            marker: Marker::Real,
+            bumps: 0,
+        })
+    }
+}
+
+/// Minimal parser state for versions.
+struct Cursor<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Cursor<'a> {
+    /// Run a parser, rewinding if it fails.
+    fn attempt<T>(&mut self, parse: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        let start = self.rest;
+        let parsed = parse(self);
+        if parsed.is_none() {
+            self.rest = start;
+        }
+        parsed
+    }
+
+    fn literal(&mut self, literal: &str) -> Option<()> {
+        self.rest = self.rest.strip_prefix(literal)?;
+        Some(())
+    }
+
+    /// Skip an optional separator.
+    fn separator(&mut self) {
+        self.rest = self.rest.strip_prefix(['.', '-', '_']).unwrap_or(self.rest);
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let end = self
+            .rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest.len());
+        let n = self.rest[..end].parse().ok()?;
+        self.rest = &self.rest[end..];
+        Some(n)
+    }
+
+    /// Consume the first matching word.
+    fn word(&mut self, words: &[&'static str]) -> Option<&'static str> {
+        let word = *words.iter().find(|w| self.rest.starts_with(**w))?;
+        self.rest = &self.rest[word.len()..];
+        Some(word)
+    }
+}
+
+/// PEP 440 version specifiers, like `>=1.0, !=1.2.*, <2`, converted to a pubgrub range.
+#[derive(Debug, Clone)]
+pub struct Specifiers {
+    /// Versions matching all the specifiers.
+    pub range: Range<Pep440Version>,
+    /// Whether one of the specifiers names a pre-release,
+    /// which allows picking pre-releases for that dependency.
+    pub prereleases: bool,
+}
+
+impl FromStr for Specifiers {
+    type Err = String;
+    fn from_str(specifiers: &str) -> Result<Self, Self::Err> {
+        let mut range = Range::any();
+        let mut prereleases = false;
+        for specifier in specifiers
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        {
+            let (specifier_range, version) = parse_specifier(specifier)?;
+            range = range.intersection(&specifier_range);
+            prereleases |= version.is_prerelease();
+        }
+        Ok(Self { range, prereleases })
+    }
+}
+
+/// Convert a single specifier into a range, also returning the version it names.
+fn parse_specifier(specifier: &str) -> Result<(Range<Pep440Version>, Pep440Version), String> {
+    let invalid = || format!("{} is not a valid PEP 440 specifier", specifier);
+    let op = *["===", "~=", "==", "!=", "<=", ">=", "<", ">"]
+        .iter()
+        .find(|op| specifier.starts_with(**op))
+        .ok_or_else(invalid)?;
+    let version = specifier[op.len()..].trim();
+
+    // Prefix matching, "==1.4.*" matches "1.4.dev0" up to, but excluding, "1.5.dev0".
+    if let Some(prefix) = version.strip_suffix(".*") {
+        let prefix = Pep440Version::from_str(prefix)?;
+        let prefix_range = Range::between(prefix.release_start(), prefix.next_release_start());
+        return match op {
+            "==" => Ok((prefix_range, prefix)),
+            "!=" => Ok((prefix_range.negate(), prefix)),
+            _ => Err(invalid()),
+        };
+    }
+
+    let v = Pep440Version::from_str(version)?;
+    // Without a local label, "==1.0" also matches "1.0+local".
+    let matching = if v.local.is_empty() {
+        Range::between(v.clone(), v.with_marker(Marker::AfterLocals))
+    } else {
+        Range::exact(v.clone())
+    };
+    let range = match op {
+        "===" => Range::exact(v.clone()),
+        "==" => matching,
+        "!=" => matching.negate(),
+        "<=" => Range::strictly_lower_than(v.with_marker(Marker::AfterLocals)),
+        ">=" => Range::higher_than(v.clone()),
+        // "<1.0" excludes "1.0rc1", unless the specifier is itself a pre-release.
+        "<" if v.is_prerelease() || v.is_postrelease() => Range::strictly_lower_than(v.clone()),
+        "<" => Range::strictly_lower_than(v.release_start()),
+        // ">1.0" excludes "1.0.post1" and "1.0+local", unless the specifier is itself a post-release.
+        ">" if v.is_prerelease() || v.is_postrelease() => {
+            Range::higher_than(v.with_marker(Marker::AfterLocals))
+        }
+        ">" => Range::higher_than(v.with_marker(Marker::AfterPosts)),
+        // "~=1.4.5" means ">=1.4.5, ==1.4.*".
+        "~=" if v.release.len() >= 2 => {
+            let mut prefix = v.release_start();
+            prefix.release.pop();
+            let prefix_range = Range::between(prefix.release_start(), prefix.next_release_start());
+            Range::higher_than(v.clone()).intersection(&prefix_range)
+        }
+        _ => return Err(invalid()),
+    };
+    Ok((range, v))
+}
+
+impl Index<Pep440Version> {
+    /// Register a Python distribution and its requirements in the index.
+    /// Requirements are given as a name, PEP 440 specifiers, and requested extras.
+    pub fn add_requirements(
+        &mut self,
+        package: &str,
+        version: &str,
+        requirements: &[(&str, &str, &[&str])],
+    ) -> Result<(), String> {
+        self.add_requirements_to(package, version, None, requirements)
+    }
+
+    /// Register an extra and its associated requirements for a given Python distribution.
+    pub fn add_extra(
+        &mut self,
+        package: &str,
+        version: &str,
+        extra: &str,
+        requirements: &[(&str, &str, &[&str])],
+    ) -> Result<(), String> {
+        self.add_requirements_to(package, version, Some(extra), requirements)
+    }
+
+    /// Convert specifiers into ranges, allowing pre-releases for the requirements naming one.
+    fn add_requirements_to(
+        &mut self,
+        package: &str,
+        version: &str,
+        extra: Option<&str>,
+        requirements: &[(&str, &str, &[&str])],
+    ) -> Result<(), String> {
+        let version = Pep440Version::from_str(version)?;
+        let mut deps = Vec::new();
+        for (name, specifiers, extras) in requirements {
+            let specifiers = Specifiers::from_str(specifiers)?;
+            let mut dep = Dep::new(specifiers.range, extras);
+            dep.prereleases = specifiers.prereleases;
+            deps.push((name.to_string(), dep));
+        }
+        self.deps_mut(package, version, extra).extend(deps);
+        Ok(())
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::optional_deps::Package;
+
+    fn v(version: &str) -> Pep440Version {
+        Pep440Version::from_str(version).unwrap()
+    }
+
+    fn spec(specifiers: &str) -> Range<Pep440Version> {
+        Specifiers::from_str(specifiers).unwrap().range
+    }
+
+    #[test]
+    /// Ordering example from PEP 440.
+    fn known_orderings() {
+        let ordered = [
+            "1.0.dev456",
+            "1.0a1",
+            "1.0a2.dev456",
+            "1.0a12.dev456",
+            "1.0a12",
+            "1.0b1.dev456",
+            "1.0b2",
+            "1.0b2.post345.dev456",
+            "1.0b2.post345",
+            "1.0rc1.dev456",
+            "1.0rc1",
+            "1.0",
+            "1.0+abc.5",
+            "1.0+abc.7",
+            "1.0+5",
+            "1.0.post456.dev34",
+            "1.0.post456",
+            "1.0.15",
+            "1.1.dev1",
+            "1!0.1",
+        ];
+        for window in ordered.windows(2) {
+            assert!(v(window[0]) < v(window[1]), "{} < {}", window[0], window[1]);
+        }
+    }
+
+    #[test]
+    fn normalization() {
+        assert_eq!(v("1.0"), v("1.0.0"));
+        assert_eq!(v("v1.0"), v("1.0"));
+        assert_eq!(v("1.0-Alpha.1"), v("1.0a1"));
+        assert_eq!(v("1.0c1"), v("1.0rc1"));
+        assert_eq!(v("1.0-1"), v("1.0.post1"));
+        assert_eq!(v("1.0.DEV"), v("1.0.dev0"));
+        assert_eq!(v("1.0+Ubuntu-1").to_string(), "1.0+ubuntu.1");
+        assert_eq!(v("0!1.0b2.post3.dev4").to_string(), "1.0b2.post3.dev4");
+        assert!(Pep440Version::from_str("1.0.foo").is_err());
+        assert!(Pep440Version::from_str("1.0+").is_err());
+    }
+
+    #[test]
+    fn prereleases() {
+        assert!(v("1.0a1").is_prerelease());
+        assert!(v("1.0.dev1").is_prerelease());
+        assert!(v("1.0.post1.dev1").is_prerelease());
+        assert!(!v("1.0.post1").is_prerelease());
+    }
+
+    #[test]
+    fn bump_is_the_successor() {
+        let bumped = v("1.0").bump();
+        assert!(v("1.0") < bumped);
+        assert!(bumped < v("1.0+0"));
+        assert!(Pep440Version::lowest() <= v("0.dev0"));
+    }
+
+    #[test]
+    fn compatible_release() {
+        let range = spec("~=1.4.5");
+        assert!(range.contains(&v("1.4.5")));
+        assert!(range.contains(&v("1.4.9.post1")));
+        assert!(!range.contains(&v("1.4.4")));
+        assert!(!range.contains(&v("1.5.0")));
+        let range = spec("~=2.2");
+        assert!(range.contains(&v("2.9")));
+        assert!(!range.contains(&v("3.0")));
+        assert!(Specifiers::from_str("~=1").is_err());
+    }
+
+    #[test]
+    fn prefix_matching() {
+        let range = spec("==1.4.*");
+        assert!(range.contains(&v("1.4")));
+        assert!(range.contains(&v("1.4.99.post1")));
+        assert!(!range.contains(&v("1.5")));
+        let range = spec(">=1.0, !=1.2.*");
+        assert!(range.contains(&v("1.1.9")));
+        assert!(!range.contains(&v("1.2.3")));
+        assert!(range.contains(&v("1.3")));
+        assert!(Specifiers::from_str(">=1.2.*").is_err());
+    }
+
+    #[test]
+    fn local_and_post_releases() {
+        let range = spec("==1.0");
+        assert!(range.contains(&v("1.0+local")));
+        assert!(!range.contains(&v("1.0.post1")));
+        assert!(spec("<=1.0").contains(&v("1.0+local")));
+        let range = spec(">1.0");
+        assert!(!range.contains(&v("1.0+local")));
+        assert!(!range.contains(&v("1.0.post1")));
+        assert!(range.contains(&v("1.0.1")));
+        assert!(spec(">1.0.post1").contains(&v("1.0.post2")));
+        assert!(spec("===1.0").contains(&v("1.0")));
+    }
+
+    #[test]
+    fn exclusive_upper_bound_excludes_prereleases() {
+        let range = spec("<2.0");
+        assert!(range.contains(&v("1.9")));
+        assert!(!range.contains(&v("2.0a1")));
+        assert!(spec("<2.0b1").contains(&v("2.0a1")));
+        assert!(Specifiers::from_str("=>1.0").is_err());
+    }
+
+    #[test]
+    fn prereleases_only_when_requested() {
+        let mut index: Index<Pep440Version> = Index::default();
+        index
+            .add_requirements("a", "1.0", &[("b", ">=1.0", &[])])
+            .unwrap();
+        index.add_requirements("b", "1.0", &[]).unwrap();
+        index.add_requirements("b", "2.0b1", &[]).unwrap();
+        let solution =
+            pubgrub::solver::resolve(&index, Package::Base("a".into()), v("1.0")).unwrap();
+        assert_eq!(solution.get(&Package::Base("b".into())), Some(&v("1.0")));
+
+        index
+            .add_requirements("a", "2.0", &[("b", ">=2.0b1", &[])])
+            .unwrap();
+        let solution =
+            pubgrub::solver::resolve(&index, Package::Base("a".into()), v("2.0")).unwrap();
+        assert_eq!(solution.get(&Package::Base("b".into())), Some(&v("2.0b1")));
+    }
+
+    #[test]
+    fn prereleases_only_for_the_requesting_dependency() {
+        let mut index: Index<Pep440Version> = Index::default();
+        index
+            .add_requirements("a", "1.0", &[("b", ">=1.0", &[])])
+            .unwrap();
+        // Not part of the resolution of a, so it does not allow pre-releases of b for a.
+        index
+            .add_requirements("other", "1.0", &[("b", ">=2.0b1", &[])])
+            .unwrap();
+        index.add_requirements("b", "1.0", &[]).unwrap();
+        index.add_requirements("b", "2.0b1", &[]).unwrap();
+        let solution =
+            pubgrub::solver::resolve(&index, Package::Base("a".into()), v("1.0")).unwrap();
+        assert_eq!(solution.get(&Package::Base("b".into())), Some(&v("1.0")));
+    }
+
+    #[test]
+    fn prereleases_when_nothing_else_matches() {
+        let mut index: Index<Pep440Version> = Index::default();
+        index
+            .add_requirements("a", "1.0", &[("b", ">=2.0", &[])])
+            .unwrap();
+        index.add_requirements("b", "1.0", &[]).unwrap();
+        index.add_requirements("b", "2.1rc1", &[]).unwrap();
+        let solution =
+            pubgrub::solver::resolve(&index, Package::Base("a".into()), v("1.0")).unwrap();
+        assert_eq!(solution.get(&Package::Base("b".into())), Some(&v("2.1rc1")));
+    }
+
+    #[test]
+    fn extras_are_features() {
+        let mut index: Index<Pep440Version> = Index::default();
+        index
+            .add_requirements("app", "1.0", &[("requests", "~=2.28", &["socks"])])
+            .unwrap();
+        index.add_requirements("requests", "2.28.1", &[]).unwrap();
+        index
+            .add_extra(
+                "requests",
+                "2.28.1",
+                "socks",
+                &[("pysocks", "!=1.5.7", &[])],
+            )
+            .unwrap();
+        index.add_requirements("pysocks", "1.5.7", &[]).unwrap();
+        index.add_requirements("pysocks", "1.7.1", &[]).unwrap();
+        let solution =
+            pubgrub::solver::resolve(&index, Package::Base("app".into()), v("1.0")).unwrap();
+        let requests_socks = Package::Feature {
+            base: "requests".into(),
+            feature: "socks".into(),
+        };
+        assert_eq!(solution.get(&requests_socks), Some(&v("2.28.1")));
+        assert_eq!(
+            solution.get(&Package::Base("pysocks".into())),
+            Some(&v("1.7.1"))
+        );
+    }
+}
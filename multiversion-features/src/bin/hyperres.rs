@@ -0,0 +1,339 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use hyperres_pubgrub_multiversion_features::dot::DotReporter;
+use hyperres_pubgrub_multiversion_features::import::import_registry;
+use hyperres_pubgrub_multiversion_features::index::{Dep, Index, PackageName, Policy};
+use hyperres_pubgrub_multiversion_features::index_file::parse_dep;
+use hyperres_pubgrub_multiversion_features::json_report::JsonReporter;
+use hyperres_pubgrub_multiversion_features::lock::Lock;
+use hyperres_pubgrub_multiversion_features::multiversion_optional_deps::Package;
+use hyperres_pubgrub_multiversion_features::repl::Repl;
+use hyperres_pubgrub_multiversion_features::resolution::Resolution;
+use hyperres_pubgrub_multiversion_features::workspace::{remove_workspace_root, WORKSPACE_ROOT};
+use pubgrub::error::PubGrubError;
+use pubgrub::report::{DerivationTree, Reporter};
+use pubgrub::type_aliases::SelectedDependencies;
+use pubgrub::version::SemanticVersion as SemVer;
+use serde_json::Value;
+use std::io::Write;
+use std::str::FromStr;
+
+const USAGE: &str = "\
+Usage: hyperres <command> [options] [requirements...]
+
+Commands:
+    resolve    Print the selected versions with their enabled features
+    explain    Explain a failure and suggest relaxations,
+               or with --package, why a package of the solution is selected
+    tree       Print the resolution as a tree
+    graph      Print the resolution, or the failure, as Graphviz DOT
+    import     Convert a Cargo registry index file to an index file
+    lock       Print the lock of the resolution
+    update     Update a package against the lock, printing the changes and the new lock
+    repl       Edit and resolve the index interactively, from an empty index by default
+
+Requirements are NAME[@REQ][:FEATURE,...], like a@^1.2:std,
+and are resolved together like the members of a workspace.
+
+Options:
+    --index FILE        Index file, in JSON
+    --registry FILE     Cargo registry index file, a JSON entry per line, instead of --index
+    --lock FILE         Lock file of a previous resolution, in JSON
+    --mode MODE         single: one version per package, without features
+                        multiversion: one version per major version, without features
+                        features: one version per major version, with features (default)
+    --json              Print failure reports as JSON
+    --package PKG       explain: package of the solution, like d#1 or d#1/alpha
+                        update: name of the package to update
+    --recursive         update: also update the locked dependencies of the package
+    --invert NAME       tree: print the dependents of a package instead
+    --duplicates        tree: print the dependents of packages selected in multiple versions
+    --encoding          graph: show the packages of the encoding, like proxies and features
+    --hypergraph        graph: show the whole encoded problem instead of its solution
+";
+
+/// How much of the index is used by the resolution.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Single,
+    Multiversion,
+    Features,
+}
+
+#[derive(Default)]
+struct Args {
+    command: String,
+    index: Option<String>,
+    registry: Option<String>,
+    lock: Option<String>,
+    mode: Option<Mode>,
+    json: bool,
+    package: Option<String>,
+    recursive: bool,
+    invert: Option<String>,
+    duplicates: bool,
+    encoding: bool,
+    hypergraph: bool,
+    /// Requirements, or the registry file of import.
+    positional: Vec<String>,
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "--help" || a == "-h") {
+        print!("{}", USAGE);
+        return;
+    }
+    if let Err(err) = parse_args(args).and_then(|args| run(&args)) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Args, String> {
+    let mut args = args.into_iter();
+    let mut parsed = Args {
+        command: args.next().unwrap_or_default(),
+        ..Args::default()
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value of {}", arg))
+        };
+        match arg.as_str() {
+            "--index" => parsed.index = Some(value()?),
+            "--registry" => parsed.registry = Some(value()?),
+            "--lock" => parsed.lock = Some(value()?),
+            "--mode" => {
+                parsed.mode = Some(match value()?.as_str() {
+                    "single" => Mode::Single,
+                    "multiversion" => Mode::Multiversion,
+                    "features" => Mode::Features,
+                    mode => return Err(format!("unknown mode {}", mode)),
+                })
+            }
+            "--json" => parsed.json = true,
+            "--package" => parsed.package = Some(value()?),
+            "--recursive" => parsed.recursive = true,
+            "--invert" => parsed.invert = Some(value()?),
+            "--duplicates" => parsed.duplicates = true,
+            "--encoding" => parsed.encoding = true,
+            "--hypergraph" => parsed.hypergraph = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ => parsed.positional.push(arg),
+        }
+    }
+    Ok(parsed)
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    if args.command == "import" {
+        let file = match &args.positional[..] {
+            [file] => file,
+            _ => return Err("import takes a single registry index file".into()),
+        };
+        let json = import_registry(&read(file)?)?;
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        return Ok(());
+    }
+    let mode = args.mode.unwrap_or(Mode::Features);
+    if args.command == "repl" {
+        let index = match (&args.index, &args.registry) {
+            (None, None) => Index::new(),
+            _ => load(args, mode)?,
+        };
+        return repl(Repl::new(index));
+    }
+    let (index, root) = load(args, mode)?.with_workspace_root(requirements(args, mode)?);
+    match args.command.as_str() {
+        "resolve" => {
+            let mut solution = resolve(&index, &root, args)?;
+            remove_workspace_root(&mut solution);
+            print!("{}", Resolution::from_solution(&solution));
+        }
+        "explain" => match pubgrub::solver::resolve(&index, root.clone(), SemVer::zero()) {
+            Ok(solution) => {
+                let package = args
+                    .package
+                    .as_deref()
+                    .ok_or("the resolution succeeded, explain a package of it with --package")?;
+                let package = Package::from_str(package)?;
+                let explanations: Vec<String> = match package {
+                    Package::Feature { .. } => index
+                        .why_feature(&solution, &root, &package)
+                        .iter()
+                        .map(|chain| chain.to_string())
+                        .collect(),
+                    _ => index
+                        .why(&solution, &root, &package)
+                        .iter()
+                        .map(|path| path.to_string())
+                        .collect(),
+                };
+                if explanations.is_empty() {
+                    return Err(format!("{} is not selected", package));
+                }
+                for explanation in explanations {
+                    println!("{}", explanation);
+                }
+            }
+            Err(PubGrubError::NoSolution(tree)) => {
+                println!("{}", report(&index, &tree, args.json));
+                let relaxations = index.relaxations(root, SemVer::zero(), &tree, 2);
+                if !relaxations.is_empty() {
+                    println!("\nThe resolution would succeed with any of:");
+                }
+                for relaxation in relaxations {
+                    let relaxation: Vec<String> =
+                        relaxation.iter().map(|r| r.to_string()).collect();
+                    println!("  - {}", relaxation.join(", "));
+                }
+            }
+            Err(err) => return Err(format!("{:?}", err)),
+        },
+        "tree" => {
+            let solution = resolve(&index, &root, args)?;
+            if let Some(name) = &args.invert {
+                print!(
+                    "{}",
+                    index.inverted_tree(&solution, &PackageName::from_str(name)?)
+                );
+            } else if args.duplicates {
+                print!("{}", index.duplicates_tree(&solution));
+            } else {
+                print!("{}", index.tree(&solution, &root));
+            }
+        }
+        "graph" if args.hypergraph => print!("{}", index.hypergraph(&root).to_dot()),
+        "graph" => match pubgrub::solver::resolve(&index, root, SemVer::zero()) {
+            Ok(mut solution) => {
+                remove_workspace_root(&mut solution);
+                print!("{}", index.solution_dot(&solution, args.encoding));
+            }
+            Err(PubGrubError::NoSolution(tree)) => print!("{}", DotReporter::report(&tree)),
+            Err(err) => return Err(format!("{:?}", err)),
+        },
+        "lock" => {
+            let mut solution = resolve(&index, &root, args)?;
+            remove_workspace_root(&mut solution);
+            print_lock(&Lock::from_solution(&solution));
+        }
+        "update" => {
+            let target = args.package.as_deref().ok_or("update needs a --package")?;
+            let target = PackageName::from_str(target)?;
+            // Locking the synthetic root keeps it out of the changes.
+            let mut index = index;
+            index.lock.add(WORKSPACE_ROOT, (0, 0, 0));
+            let updated = index
+                .update(root, SemVer::zero(), target, args.recursive)
+                .map_err(|err| failure(&index, err, args.json))?;
+            for change in &updated.changes {
+                eprintln!("{}", change);
+            }
+            let mut lock = updated.lock;
+            lock.packages.remove(&PackageName::registry(WORKSPACE_ROOT));
+            print_lock(&lock);
+        }
+        command => return Err(format!("unknown command {}\n\n{}", command, USAGE)),
+    }
+    Ok(())
+}
+
+/// Execute the commands read from the standard input until its end or "quit".
+fn repl(mut repl: Repl) -> Result<(), String> {
+    let stdin = std::io::stdin();
+    loop {
+        print!("> ");
+        let _ = std::io::stdout().flush();
+        let mut line = String::new();
+        if stdin.read_line(&mut line).map_err(|e| e.to_string())? == 0 || line.trim() == "quit" {
+            return Ok(());
+        }
+        match repl.execute(&line) {
+            Ok(output) => print!("{}", output),
+            Err(err) => println!("error: {}", err),
+        }
+    }
+}
+
+/// Load the index in the given mode, with its lock.
+fn load(args: &Args, mode: Mode) -> Result<Index, String> {
+    let json = match (&args.index, &args.registry) {
+        (Some(file), None) => parse_json(&read(file)?)?,
+        (None, Some(file)) => import_registry(&read(file)?)?,
+        _ => return Err("either --index or --registry is needed".into()),
+    };
+    let mut index = Index::from_json(&json)?;
+    if let Some(file) = &args.lock {
+        index.lock = Lock::from_json(&parse_json(&read(file)?)?)?;
+    }
+    if mode == Mode::Single {
+        index.default_policy = Policy::Single;
+        index.policies.clear();
+    }
+    if mode != Mode::Features {
+        for deps in index.packages.values_mut().flat_map(|v| v.values_mut()) {
+            deps.optional.clear();
+            for dep in deps.mandatory.values_mut() {
+                dep.features.clear();
+            }
+        }
+    }
+    Ok(index)
+}
+
+/// Parse the root requirements.
+fn requirements(args: &Args, mode: Mode) -> Result<Vec<Dep>, String> {
+    if args.positional.is_empty() {
+        return Err("no requirements to resolve".into());
+    }
+    let mut requirements = Vec::new();
+    for requirement in &args.positional {
+        let mut dep = parse_dep(requirement)?;
+        if mode != Mode::Features {
+            dep.features.clear();
+        }
+        requirements.push(dep);
+    }
+    Ok(requirements)
+}
+
+/// Resolve from the workspace root, the failure report being the error.
+fn resolve(
+    index: &Index,
+    root: &Package,
+    args: &Args,
+) -> Result<SelectedDependencies<Package, SemVer>, String> {
+    pubgrub::solver::resolve(index, root.clone(), SemVer::zero())
+        .map_err(|err| failure(index, err, args.json))
+}
+
+fn failure(index: &Index, err: PubGrubError<Package, SemVer>, json: bool) -> String {
+    match err {
+        PubGrubError::NoSolution(tree) => {
+            format!("failed to resolve\n\n{}", report(index, &tree, json))
+        }
+        err => format!("{:?}", err),
+    }
+}
+
+fn report(index: &Index, tree: &DerivationTree<Package, SemVer>, json: bool) -> String {
+    if json {
+        serde_json::to_string_pretty(&JsonReporter::report_for_index(index, tree)).unwrap()
+    } else {
+        index.report(tree)
+    }
+}
+
+fn print_lock(lock: &Lock) {
+    println!("{}", serde_json::to_string_pretty(&lock.to_json()).unwrap());
+}
+
+fn read(file: &str) -> Result<String, String> {
+    std::fs::read_to_string(file).map_err(|e| format!("cannot read {}: {}", file, e))
+}
+
+fn parse_json(content: &str) -> Result<Value, String> {
+    serde_json::from_str(content).map_err(|e| e.to_string())
+}
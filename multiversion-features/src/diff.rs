@@ -0,0 +1,200 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{Feature, PackageName};
+use crate::resolution::Resolution;
+use crate::update::{changes, Change};
+use core::fmt::Display;
+use pubgrub::version::SemanticVersion as SemVer;
+use serde_json::{json, Value};
+use std::collections::BTreeSet as Set;
+
+/// Differences between two resolutions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolutionDiff {
+    /// Version changes, with versions paired by bucket first like for an update.
+    pub changes: Vec<Change>,
+    /// Changes of the enabled features of package versions kept or updated.
+    pub features: Vec<FeatureChange>,
+    /// Packages selected in multiple versions in the new resolution only.
+    pub new_duplicates: Vec<PackageName>,
+    /// Packages selected in multiple versions in the old resolution only.
+    pub removed_duplicates: Vec<PackageName>,
+}
+
+/// Features enabled or not anymore for a package version,
+/// compared to the same version or to the one it was updated from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureChange {
+    pub package: PackageName,
+    /// The version in the new resolution.
+    pub version: SemVer,
+    pub added: Set<Feature>,
+    pub removed: Set<Feature>,
+}
+
+impl ResolutionDiff {
+    /// Differences from an old resolution to a new one.
+    pub fn new(old: &Resolution, new: &Resolution) -> Self {
+        let changes = changes(&old.lock(), &new.lock());
+        let mut features = Vec::new();
+        // Versions kept are compared to themselves, and updated versions to their old version.
+        let kept = new.packages.iter().flat_map(|(name, versions)| {
+            versions
+                .keys()
+                .filter(move |v| old.features(name, v).is_some())
+                .map(move |v| (name, *v, *v))
+        });
+        let updated = changes.iter().filter_map(|change| match change {
+            Change::Updated { package, from, to } => Some((package, *from, *to)),
+            _ => None,
+        });
+        let mut pairs: Vec<_> = kept.chain(updated).collect();
+        pairs.sort();
+        for (package, from, to) in pairs {
+            let (before, after) = match (old.features(package, &from), new.features(package, &to)) {
+                (Some(before), Some(after)) => (before, after),
+                _ => continue,
+            };
+            if before != after {
+                features.push(FeatureChange {
+                    package: package.clone(),
+                    version: to,
+                    added: after.difference(before).cloned().collect(),
+                    removed: before.difference(after).cloned().collect(),
+                });
+            }
+        }
+        let old_duplicates: Set<&PackageName> = old.duplicates().collect();
+        let new_duplicates: Set<&PackageName> = new.duplicates().collect();
+        Self {
+            changes,
+            features,
+            new_duplicates: new_duplicates
+                .difference(&old_duplicates)
+                .map(|&n| n.clone())
+                .collect(),
+            removed_duplicates: old_duplicates
+                .difference(&new_duplicates)
+                .map(|&n| n.clone())
+                .collect(),
+        }
+    }
+
+    /// Whether both resolutions are the same.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// JSON serialization of the differences.
+    pub fn to_json(&self) -> Value {
+        let changes: Vec<Value> = self
+            .changes
+            .iter()
+            .map(|change| match change {
+                Change::Added(package, version) => json!({
+                    "kind": "added",
+                    "package": package.to_string(),
+                    "version": version.to_string(),
+                }),
+                Change::Removed(package, version) => json!({
+                    "kind": "removed",
+                    "package": package.to_string(),
+                    "version": version.to_string(),
+                }),
+                Change::Updated { package, from, to } => json!({
+                    "kind": "updated",
+                    "package": package.to_string(),
+                    "from": from.to_string(),
+                    "to": to.to_string(),
+                }),
+            })
+            .collect();
+        let features: Vec<Value> = self
+            .features
+            .iter()
+            .map(|change| {
+                json!({
+                    "package": change.package.to_string(),
+                    "version": change.version.to_string(),
+                    "added": change.added,
+                    "removed": change.removed,
+                })
+            })
+            .collect();
+        let names = |names: &[PackageName]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        json!({
+            "changes": changes,
+            "features": features,
+            "new_duplicates": names(&self.new_duplicates),
+            "removed_duplicates": names(&self.removed_duplicates),
+        })
+    }
+}
+
+impl Display for ResolutionDiff {
+    /// One line per difference, like "Updating d v1.0.0 -> v1.1.0",
+    /// "Features of d v1.1.0: +alpha -beta" or "Duplicating d".
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        for change in &self.features {
+            write!(f, "Features of {} v{}:", change.package, change.version)?;
+            for feature in &change.added {
+                write!(f, " +{}", feature)?;
+            }
+            for feature in &change.removed {
+                write!(f, " -{}", feature)?;
+            }
+            writeln!(f)?;
+        }
+        for name in &self.new_duplicates {
+            writeln!(f, "Duplicating {}", name)?;
+        }
+        for name in &self.removed_duplicates {
+            writeln!(f, "No longer duplicating {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn differences_between_resolutions() {
+        let mut old = Resolution::new();
+        old.add("a", (1, 0, 0), &[]);
+        old.add("b", (1, 0, 0), &["x", "y"]);
+        old.add("d", (1, 0, 0), &[]);
+        old.add("d", (2, 0, 0), &[]);
+        let mut new = Resolution::new();
+        new.add("a", (1, 0, 0), &["std"]);
+        new.add("b", (1, 1, 0), &["x", "z"]);
+        new.add("c", (1, 0, 0), &[]);
+        new.add("c", (2, 0, 0), &[]);
+        new.add("d", (2, 0, 0), &[]);
+        let diff = ResolutionDiff::new(&old, &new);
+        assert_eq!(
+            diff.to_string(),
+            "\
+Updating b v1.0.0 -> v1.1.0
+Adding c v1.0.0
+Adding c v2.0.0
+Removing d v1.0.0
+Features of a v1.0.0: +std
+Features of b v1.1.0: +z -y
+Duplicating c
+No longer duplicating d
+"
+        );
+        let json = diff.to_json();
+        assert_eq!(json["changes"][0]["kind"], "updated");
+        assert_eq!(json["features"][1]["removed"], json!(["y"]));
+        assert_eq!(json["new_duplicates"], json!(["c"]));
+        assert!(ResolutionDiff::new(&new, &new).is_empty());
+    }
+}
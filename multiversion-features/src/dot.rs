@@ -0,0 +1,370 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{Index, PackageName};
+use crate::multiversion_optional_deps::Package;
+use crate::reporter::{collapse_proxies, UserTerms};
+use pubgrub::report::{DerivationTree, Reporter};
+use pubgrub::type_aliases::SelectedDependencies;
+use pubgrub::version::SemanticVersion as SemVer;
+use std::collections::{BTreeMap as Map, BTreeSet as Set};
+use std::fmt::Write;
+
+/// Dependent and dependency nodes of an edge, with the feature of the dependent requiring it.
+type EdgeKey<'s> = (String, String, Option<&'s str>);
+
+impl Index {
+    /// Graphviz DOT rendering of a solution.
+    ///
+    /// There is one node per selected package version, labelled with its enabled features,
+    /// and the buckets of a package selected more than once are clustered together.
+    /// Edges are labelled with the requested range and features,
+    /// and dashed for dependencies of a feature, labelled with that feature.
+    ///
+    /// With `show_encoding`, the packages of the resolution are drawn instead,
+    /// including the proxies, features and markers encoding multiple versions and features.
+    pub fn solution_dot(
+        &self,
+        solution: &SelectedDependencies<Package, SemVer>,
+        show_encoding: bool,
+    ) -> String {
+        if show_encoding {
+            self.encoding_dot(solution)
+        } else {
+            self.versions_dot(solution)
+        }
+    }
+
+    fn versions_dot(&self, solution: &SelectedDependencies<Package, SemVer>) -> String {
+        // Enabled features of each selected version, per package.
+        let mut nodes: Map<&PackageName, Map<SemVer, Set<&str>>> = Map::new();
+        // Requested range and features of each dependency.
+        let mut edges: Map<EdgeKey, (String, Set<String>)> = Map::new();
+        for (package, version) in solution {
+            let (base, feature) = match package {
+                Package::Bucket(base) => (base, None),
+                Package::Feature { base, feature } => (base, Some(feature.as_str())),
+                _ => continue,
+            };
+            let features = nodes
+                .entry(&base.name)
+                .or_default()
+                .entry(*version)
+                .or_default();
+            features.extend(feature);
+            for hop in self.selected_hops(solution, package, *version) {
+                let (dep, dep_feature) = match &hop.package {
+                    Package::Bucket(dep) => (dep, None),
+                    Package::Feature { base, feature } => (base, Some(feature.as_str())),
+                    _ => continue,
+                };
+                // Features of a package enabling its base, or other features of the same version.
+                if dep.name == base.name && hop.version == *version {
+                    continue;
+                }
+                let key = (
+                    node_id(&base.name, version),
+                    node_id(&dep.name, &hop.version),
+                    feature,
+                );
+                let (_, requested) = edges
+                    .entry(key)
+                    .or_insert_with(|| (hop.range.to_string(), Set::new()));
+                requested.extend(dep_feature.map(str::to_string));
+            }
+        }
+
+        let mut dot = String::from("digraph solution {\n    node [shape=box];\n");
+        for (name, versions) in &nodes {
+            let indent = if versions.len() > 1 {
+                let _ = writeln!(
+                    dot,
+                    "    subgraph {} {{",
+                    quote(&format!("cluster_{}", name))
+                );
+                let _ = writeln!(dot, "        label={};", quote(&name.to_string()));
+                "        "
+            } else {
+                "    "
+            };
+            for (version, features) in versions {
+                let mut label = format!("{} {}", name, version);
+                if !features.is_empty() {
+                    let features: Vec<&str> = features.iter().copied().collect();
+                    let _ = write!(label, "\n[{}]", features.join(", "));
+                }
+                let _ = writeln!(
+                    dot,
+                    "{}{} [label={}];",
+                    indent,
+                    quote(&node_id(name, version)),
+                    quote(&label)
+                );
+            }
+            if versions.len() > 1 {
+                dot.push_str("    }\n");
+            }
+        }
+        for ((from, to, feature), (range, features)) in &edges {
+            let mut label = range.clone();
+            if !features.is_empty() {
+                let features: Vec<&str> = features.iter().map(String::as_str).collect();
+                let _ = write!(label, "\n[{}]", features.join(", "));
+            }
+            let style = match feature {
+                None => String::new(),
+                Some(feature) => {
+                    label = format!("{}: {}", feature, label);
+                    ", style=dashed".to_string()
+                }
+            };
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [label={}{}];",
+                quote(from),
+                quote(to),
+                quote(&label),
+                style
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn encoding_dot(&self, solution: &SelectedDependencies<Package, SemVer>) -> String {
+        let mut packages: Vec<(String, &Package, &SemVer)> = solution
+            .iter()
+            .map(|(package, version)| (format!("{} {}", package, version), package, version))
+            .collect();
+        packages.sort_by(|(id1, ..), (id2, ..)| id1.cmp(id2));
+        let mut dot = String::from("digraph resolution {\n");
+        for (id, package, _) in &packages {
+            let _ = writeln!(dot, "    {} [shape={}];", quote(id), shape(package));
+        }
+        for (id, package, version) in &packages {
+            let mut deps: Vec<(String, String)> = self
+                .selected_dependencies(solution, package, **version)
+                .into_iter()
+                .map(|(dep, range)| (format!("{} {}", dep, solution[&dep]), range.to_string()))
+                .collect();
+            deps.sort();
+            for (dep_id, range) in deps {
+                let _ = writeln!(
+                    dot,
+                    "    {} -> {} [label={}];",
+                    quote(id),
+                    quote(&dep_id),
+                    quote(&range)
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Reporter drawing the derivation tree of a failure as a Graphviz DOT graph,
+/// with terms translated like the [UserReporter](crate::reporter::UserReporter) does.
+///
+/// External incompatibilities are boxes and derived ones are ellipses,
+/// with an edge from each derived incompatibility to its two causes.
+/// Derived incompatibilities shared by several parts of the tree are drawn once, filled,
+/// and the root, the final failure, is drawn in bold.
+pub struct DotReporter;
+
+impl Reporter<Package, SemVer> for DotReporter {
+    type Output = String;
+
+    fn report(derivation_tree: &DerivationTree<Package, SemVer>) -> Self::Output {
+        let mut graph = TreeGraph {
+            user: UserTerms::new(derivation_tree),
+            dot: String::from("digraph derivation_tree {\n"),
+            node_count: 0,
+            drawn_shared: Set::new(),
+        };
+        let root = graph.node(&collapse_proxies(derivation_tree.clone()));
+        let _ = writeln!(graph.dot, "    {} [style=bold];", root);
+        graph.dot.push_str("}\n");
+        graph.dot
+    }
+}
+
+/// DOT graph of a derivation tree being drawn.
+struct TreeGraph {
+    user: UserTerms,
+    dot: String,
+    /// Number of nodes drawn so far, to identify nodes that are not shared.
+    node_count: usize,
+    /// Shared nodes drawn so far.
+    drawn_shared: Set<usize>,
+}
+
+impl TreeGraph {
+    /// Draw a node of the derivation tree and its causes, returning the node identifier.
+    fn node(&mut self, tree: &DerivationTree<Package, SemVer>) -> String {
+        self.node_count += 1;
+        match tree {
+            DerivationTree::External(external) => {
+                let id = format!("external{}", self.node_count);
+                let label = quote(&self.user.external(external));
+                let _ = writeln!(self.dot, "    {} [shape=box, label={}];", id, label);
+                id
+            }
+            DerivationTree::Derived(derived) => {
+                let (id, style) = match derived.shared_id {
+                    Some(shared) => {
+                        let id = format!("shared{}", shared);
+                        if !self.drawn_shared.insert(shared) {
+                            return id;
+                        }
+                        (id, ", style=filled, fillcolor=lightblue")
+                    }
+                    None => (format!("derived{}", self.node_count), ""),
+                };
+                let label = quote(&self.user.terms(&derived.terms));
+                let _ = writeln!(self.dot, "    {} [label={}{}];", id, label, style);
+                let cause1 = self.node(&derived.cause1);
+                let cause2 = self.node(&derived.cause2);
+                let _ = writeln!(self.dot, "    {} -> {};", id, cause1);
+                let _ = writeln!(self.dot, "    {} -> {};", id, cause2);
+                id
+            }
+        }
+    }
+}
+
+/// Identifier of the node of a package version.
+fn node_id(name: &PackageName, version: &SemVer) -> String {
+    format!("{} {}", name, version)
+}
+
+/// Shape of the node of a package of the encoding.
+pub(crate) fn shape(package: &Package) -> &'static str {
+    match package {
+        Package::Bucket(_) => "box",
+        Package::Feature { .. } => "ellipse",
+        Package::Proxy { .. } => "diamond",
+        Package::Singleton(_) | Package::Links(_) | Package::SlotOf(_) | Package::Slot { .. } => {
+            "octagon"
+        }
+    }
+}
+
+/// Quoted DOT identifier, with line breaks in labels.
+pub(crate) fn quote(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use pubgrub::range::Range;
+    use pubgrub::report::{Derived, External};
+    use pubgrub::term::Term;
+    use std::str::FromStr;
+    type R = core::ops::RangeFull;
+
+    fn index() -> Index {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &["alpha"])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (2, 0, 0)..(4, 0, 0), &[])]);
+        index.add_feature("d", (1, 0, 0), "alpha", &[("e", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps::<R>("d", (2, 0, 0), &[]);
+        index.add_deps::<R>("e", (1, 0, 0), &[]);
+        index
+    }
+
+    fn solution(index: &Index) -> SelectedDependencies<Package, SemVer> {
+        pubgrub::solver::resolve(index, Package::from_str("a#1").unwrap(), (1, 0, 0)).unwrap()
+    }
+
+    #[test]
+    fn versions_graph() {
+        let index = index();
+        let dot = index.solution_dot(&solution(&index), false);
+        assert!(dot.starts_with("digraph solution {\n"));
+        assert!(dot.contains("    subgraph \"cluster_d\" {\n        label=\"d\";\n"));
+        assert!(dot.contains("        \"d 1.0.0\" [label=\"d 1.0.0\\n[alpha]\"];\n"));
+        assert!(dot.contains("    \"a 1.0.0\" [label=\"a 1.0.0\"];\n"));
+        assert!(dot.contains(
+            "    \"b 1.0.0\" -> \"d 1.0.0\" [label=\"1.0.0 <= v < 2.0.0\\n[alpha]\"];\n"
+        ));
+        // The proxy of c is seen through, with the range c requested.
+        assert!(dot.contains("    \"c 1.0.0\" -> \"d 2.0.0\" [label=\"2.0.0 <= v < 4.0.0\"];\n"));
+        assert!(dot.contains(
+            "    \"d 1.0.0\" -> \"e 1.0.0\" [label=\"alpha: 1.0.0 <= v < 2.0.0\", style=dashed];\n"
+        ));
+        assert!(!dot.contains("->d"));
+    }
+
+    #[test]
+    fn encoding_graph() {
+        let index = index();
+        let dot = index.solution_dot(&solution(&index), true);
+        assert!(dot.contains("    \"c#1@1.0.0->d 2.0.0\" [shape=diamond];\n"));
+        assert!(dot.contains("    \"d#1/alpha 1.0.0\" [shape=ellipse];\n"));
+        assert!(dot.contains("    \"c#1 1.0.0\" -> \"c#1@1.0.0->d 2.0.0\" [label=\"∗\"];\n"));
+        assert!(dot.contains("    \"d#1/alpha 1.0.0\" -> \"d#1 1.0.0\" [label=\"1.0.0\"];\n"));
+    }
+
+    #[test]
+    fn shared_derivations_are_drawn_once() {
+        let a = Package::from_str("a#1").unwrap();
+        let b = Package::from_str("b#1").unwrap();
+        let mut terms = pubgrub::type_aliases::Map::default();
+        terms.insert(a.clone(), Term::Positive(Range::exact((1, 0, 0))));
+        let no_b = DerivationTree::External(External::NoVersions(b.clone(), Range::any()));
+        let shared = DerivationTree::Derived(Derived {
+            terms,
+            shared_id: Some(7),
+            cause1: Box::new(DerivationTree::External(External::FromDependencyOf(
+                a.clone(),
+                Range::exact((1, 0, 0)),
+                b,
+                Range::between((1, 0, 0), (2, 0, 0)),
+            ))),
+            cause2: Box::new(no_b),
+        });
+        let tree = DerivationTree::Derived(Derived {
+            terms: pubgrub::type_aliases::Map::default(),
+            shared_id: None,
+            cause1: Box::new(shared.clone()),
+            cause2: Box::new(shared),
+        });
+        let dot = DotReporter::report(&tree);
+        assert!(dot.starts_with("digraph derivation_tree {\n"));
+        assert_eq!(dot.matches("    shared7 [").count(), 1);
+        assert_eq!(dot.matches(" -> shared7;").count(), 2);
+        assert!(dot.contains("    derived1 [label=\"version solving failed\"];\n"));
+        assert!(dot.contains("    derived1 [style=bold];\n"));
+        assert!(dot.contains(", style=filled, fillcolor=lightblue];"));
+        assert!(dot.contains("[shape=box, label=\"a 1.0.0 depends on b "));
+    }
+
+    #[test]
+    fn workspace_root_is_the_requirements() {
+        let mut index = index();
+        index.add_singleton("d");
+        let (index, root) = index.with_workspace_root([crate::index::Dep {
+            package: "a".into(),
+            range: Range::any(),
+            features: Default::default(),
+        }]);
+        let tree = match pubgrub::solver::resolve(&index, root, (0, 0, 0)) {
+            Err(pubgrub::error::PubGrubError::NoSolution(tree)) => tree,
+            _ => panic!("expected no solution"),
+        };
+        let dot = DotReporter::report(&tree);
+        assert!(!dot.contains(crate::workspace::WORKSPACE_ROOT));
+        assert!(
+            dot.contains("[shape=box, label=\"the requirements ask for a 1.0.0 <= v < 2.0.0\"];")
+        );
+    }
+}
@@ -0,0 +1,227 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::dot::{quote, shape};
+use crate::index::Index;
+use crate::multiversion_optional_deps::Package;
+use pubgrub::range::Range;
+use pubgrub::solver::{Dependencies, DependencyProvider};
+use pubgrub::version::SemanticVersion as SemVer;
+use serde_json::{json, Value};
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Write;
+
+/// The part of the encoded resolution problem reachable from a root package,
+/// before solving: the packages of the encoding, including proxies, features and markers,
+/// with their versions, and the constraints between them.
+#[derive(Debug, Clone)]
+pub struct Hypergraph {
+    /// Reachable packages in breadth-first order, with their versions in the order they are tried.
+    pub packages: Vec<(Package, Vec<SemVer>)>,
+    /// Constraints of each package version with known dependencies.
+    pub hyperedges: Vec<Hyperedge>,
+}
+
+/// The dependencies of a package version, a hyperedge from that version
+/// to a range of versions of each of its dependencies.
+#[derive(Debug, Clone)]
+pub struct Hyperedge {
+    pub package: Package,
+    pub version: SemVer,
+    /// Dependencies sorted by package.
+    pub dependencies: Vec<(Package, Range<SemVer>)>,
+}
+
+impl Index {
+    /// Walk the dependencies of every version of the packages reachable from a root package,
+    /// as the solver would ask for them.
+    pub fn hypergraph(&self, root: &Package) -> Hypergraph {
+        let mut hypergraph = Hypergraph {
+            packages: Vec::new(),
+            hyperedges: Vec::new(),
+        };
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([root.clone()]);
+        while let Some(package) = queue.pop_front() {
+            if !visited.insert(package.clone()) {
+                continue;
+            }
+            let versions: Vec<SemVer> = self.list_versions(&package).collect();
+            for version in &versions {
+                if let Ok(Dependencies::Known(deps)) = self.get_dependencies(&package, version) {
+                    let mut dependencies: Vec<_> = deps.into_iter().collect();
+                    dependencies.sort_by_key(|(dep, _)| dep.to_string());
+                    for (dep, _) in &dependencies {
+                        if !visited.contains(dep) {
+                            queue.push_back(dep.clone());
+                        }
+                    }
+                    hypergraph.hyperedges.push(Hyperedge {
+                        package: package.clone(),
+                        version: *version,
+                        dependencies,
+                    });
+                }
+            }
+            hypergraph.packages.push((package, versions));
+        }
+        hypergraph
+    }
+}
+
+impl Hypergraph {
+    /// Number of package versions, the nodes the solver may pick.
+    pub fn version_count(&self) -> usize {
+        self.packages
+            .iter()
+            .map(|(_, versions)| versions.len())
+            .sum()
+    }
+
+    /// Number of dependency constraints over all hyperedges.
+    pub fn constraint_count(&self) -> usize {
+        self.hyperedges.iter().map(|e| e.dependencies.len()).sum()
+    }
+
+    /// JSON serialization, with the sizes of the encoding in "stats".
+    /// Packages are identified by their encoded name, like "d#1", "d#1/alpha" or "a#1@1.0.0->d".
+    pub fn to_json(&self) -> Value {
+        let packages: Vec<Value> = self
+            .packages
+            .iter()
+            .map(|(package, versions)| {
+                json!({
+                    "package": package.to_string(),
+                    "kind": kind(package),
+                    "versions": versions.iter().map(|v| v.to_string()).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        let hyperedges: Vec<Value> = self
+            .hyperedges
+            .iter()
+            .map(|edge| {
+                let dependencies: Vec<Value> = edge
+                    .dependencies
+                    .iter()
+                    .map(|(dep, range)| {
+                        json!({
+                            "package": dep.to_string(),
+                            "range": range.to_string(),
+                        })
+                    })
+                    .collect();
+                json!({
+                    "package": edge.package.to_string(),
+                    "version": edge.version.to_string(),
+                    "dependencies": dependencies,
+                })
+            })
+            .collect();
+        json!({
+            "packages": packages,
+            "hyperedges": hyperedges,
+            "stats": {
+                "packages": self.packages.len(),
+                "versions": self.version_count(),
+                "hyperedges": self.hyperedges.len(),
+                "constraints": self.constraint_count(),
+            },
+        })
+    }
+
+    /// Graphviz DOT rendering, with a node per package shaped like its kind,
+    /// and a point per hyperedge, attached to its package by a dashed edge labelled with
+    /// the version, and to each dependency by an edge labelled with the range.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph hypergraph {\n");
+        for (package, _) in &self.packages {
+            let id = quote(&package.to_string());
+            let _ = writeln!(dot, "    {} [shape={}];", id, shape(package));
+        }
+        for (i, edge) in self.hyperedges.iter().enumerate() {
+            let package = quote(&edge.package.to_string());
+            let _ = writeln!(dot, "    edge{} [shape=point];", i);
+            let version = quote(&edge.version.to_string());
+            let _ = writeln!(
+                dot,
+                "    {} -> edge{} [label={}, style=dashed, arrowhead=none];",
+                package, i, version
+            );
+            for (dep, range) in &edge.dependencies {
+                let dep = quote(&dep.to_string());
+                let range = quote(&range.to_string());
+                let _ = writeln!(dot, "    edge{} -> {} [label={}];", i, dep, range);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Kind of a package of the encoding.
+fn kind(package: &Package) -> &'static str {
+    match package {
+        Package::Bucket(_) => "bucket",
+        Package::Feature { .. } => "feature",
+        Package::Proxy { .. } => "proxy",
+        Package::Singleton(_) => "singleton",
+        Package::Links(_) => "links",
+        Package::SlotOf(_) => "slot_of",
+        Package::Slot { .. } => "slot",
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::str::FromStr;
+    type R = core::ops::RangeFull;
+
+    fn hypergraph() -> Hypergraph {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(3, 0, 0), &["x"])]);
+        index.add_feature::<R>("b", (1, 0, 0), "x", &[]);
+        index.add_feature::<R>("b", (2, 0, 0), "x", &[]);
+        index.add_deps::<R>("c", (1, 0, 0), &[]);
+        index.hypergraph(&Package::from_str("a#1").unwrap())
+    }
+
+    #[test]
+    fn reachable_encoding() {
+        let hypergraph = hypergraph();
+        let packages: Vec<String> = hypergraph
+            .packages
+            .iter()
+            .map(|(package, _)| package.to_string())
+            .collect();
+        // The proxy has one version per bucket, pointing to the feature of each bucket,
+        // and the unreachable c is left out.
+        assert_eq!(
+            packages,
+            vec!["a#1", "a#1@1.0.0/x->b", "b#2/x", "b#1/x", "b#2", "b#1"]
+        );
+        assert_eq!(hypergraph.version_count(), 7);
+        assert_eq!(hypergraph.hyperedges.len(), 7);
+        assert_eq!(hypergraph.constraint_count(), 5);
+    }
+
+    #[test]
+    fn json_and_dot_exports() {
+        let hypergraph = hypergraph();
+        let json = hypergraph.to_json();
+        assert_eq!(json["stats"]["constraints"], 5);
+        assert_eq!(json["packages"][1]["kind"], "proxy");
+        assert_eq!(
+            json["hyperedges"][0]["dependencies"][0]["package"],
+            "a#1@1.0.0/x->b"
+        );
+        let dot = hypergraph.to_dot();
+        assert!(dot.contains("    \"a#1@1.0.0/x->b\" [shape=diamond];\n"));
+        assert!(
+            dot.contains("    \"a#1\" -> edge0 [label=\"1.0.0\", style=dashed, arrowhead=none];\n")
+        );
+        assert!(dot.contains("    edge0 -> \"a#1@1.0.0/x->b\" [label=\"∗\"];\n"));
+    }
+}
@@ -0,0 +1,203 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use serde_json::{json, Map as JsonMap, Value};
+
+/// Convert the entries of a Cargo registry index, a JSON object per line and per version,
+/// into the index file format read by `Index::from_json`.
+///
+/// Development dependencies and pre-release versions are skipped.
+/// Optional dependencies become dependencies of the features enabling them,
+/// with an implicit feature for each optional dependency not enabled with the "dep:" syntax.
+/// A feature enabling other features of the same package depends on its own version
+/// with those features, and weak "dep?/feature" features of optional dependencies are ignored.
+/// Default features are not requested by dependents.
+pub fn import_registry(lines: &str) -> Result<Value, String> {
+    let mut packages = JsonMap::new();
+    let mut links = JsonMap::new();
+    for (i, line) in lines.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Value =
+            serde_json::from_str(line).map_err(|e| format!("line {}: {}", i + 1, e))?;
+        let name = field(&entry, "name", i)?;
+        let version = field(&entry, "vers", i)?;
+        // Build metadata does not distinguish versions.
+        let version = version.split('+').next().unwrap_or(version);
+        if version.contains('-') {
+            continue;
+        }
+        let versions = packages
+            .entry(name)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .unwrap();
+        versions.insert(
+            version.to_string(),
+            import_version(&entry, name, version, i)?,
+        );
+        if let Some(l) = entry.get("links").and_then(Value::as_str) {
+            links.insert(name.to_string(), json!(l));
+        }
+    }
+    Ok(json!({ "packages": packages, "links": links }))
+}
+
+/// A version of the index file format from a registry entry.
+fn import_version(entry: &Value, name: &str, version: &str, line: usize) -> Result<Value, String> {
+    let mut mandatory = JsonMap::new();
+    let mut optional = JsonMap::new();
+    for dep in entry
+        .get("deps")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if dep.get("kind").and_then(Value::as_str) == Some("dev") {
+            continue;
+        }
+        let alias = field(dep, "name", line)?;
+        let package = dep.get("package").and_then(Value::as_str).unwrap_or(alias);
+        let features = dep.get("features").cloned().unwrap_or_else(|| json!([]));
+        let requirement = json!({
+            "package": package,
+            "req": field(dep, "req", line)?,
+            "features": features,
+        });
+        if dep.get("optional").and_then(Value::as_bool) == Some(true) {
+            optional.insert(alias.to_string(), requirement);
+        } else {
+            mandatory.insert(alias.to_string(), requirement);
+        }
+    }
+    let declared = ["features", "features2"]
+        .iter()
+        .filter_map(|key| entry.get(*key).and_then(Value::as_object))
+        .flatten();
+    let mut features = JsonMap::new();
+    let mut explicit_deps = Vec::new();
+    for (feature, enabled) in declared {
+        let mut deps = JsonMap::new();
+        for item in enabled.as_array().into_iter().flatten() {
+            let item = item.as_str().unwrap_or_default();
+            if let Some(dep) = item.strip_prefix("dep:") {
+                explicit_deps.push(dep.to_string());
+                let requirement = optional.get(dep).ok_or_else(|| unknown(dep, line))?;
+                enable(&mut deps, dep, requirement, None);
+            } else if let Some((dep, dep_feature)) = item.split_once('/') {
+                let (dep, weak) = match dep.strip_suffix('?') {
+                    Some(dep) => (dep, true),
+                    None => (dep, false),
+                };
+                if let Some(requirement) = mandatory.get(dep) {
+                    enable(&mut deps, dep, requirement, Some(dep_feature));
+                } else if let Some(requirement) = optional.get(dep) {
+                    if !weak {
+                        enable(&mut deps, dep, requirement, Some(dep_feature));
+                    }
+                } else {
+                    return Err(unknown(dep, line));
+                }
+            } else if let Some(requirement) = optional.get(item) {
+                enable(&mut deps, item, requirement, None);
+            } else {
+                let itself = json!({ "package": name, "req": format!("={}", version) });
+                enable(&mut deps, name, &itself, Some(item));
+            }
+        }
+        features.insert(feature.clone(), Value::Object(deps));
+    }
+    for (dep, requirement) in &optional {
+        if !explicit_deps.contains(dep) && !features.contains_key(dep) {
+            features.insert(dep.clone(), json!({ dep.clone(): requirement }));
+        }
+    }
+    let status = match entry.get("yanked").and_then(Value::as_bool) {
+        Some(true) => "yanked",
+        _ => "available",
+    };
+    Ok(json!({
+        "dependencies": mandatory,
+        "features": features,
+        "status": status,
+    }))
+}
+
+/// Add a dependency to the dependencies of a feature, with an additional feature.
+fn enable(
+    deps: &mut JsonMap<String, Value>,
+    alias: &str,
+    requirement: &Value,
+    feature: Option<&str>,
+) {
+    let dep = deps.entry(alias).or_insert_with(|| requirement.clone());
+    if let Some(feature) = feature {
+        let features = dep
+            .as_object_mut()
+            .unwrap()
+            .entry("features")
+            .or_insert_with(|| json!([]))
+            .as_array_mut()
+            .unwrap();
+        if !features.iter().any(|f| f == feature) {
+            features.push(json!(feature));
+        }
+    }
+}
+
+fn field<'e>(entry: &'e Value, key: &str, line: usize) -> Result<&'e str, String> {
+    entry
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("line {}: missing {}", line + 1, key))
+}
+
+fn unknown(dep: &str, line: usize) -> String {
+    format!(
+        "line {}: feature enabling unknown dependency {}",
+        line + 1,
+        dep
+    )
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::index::Index;
+    use crate::lock::Lock;
+    use crate::multiversion_optional_deps::Package;
+    use std::str::FromStr;
+
+    const REGISTRY: &str = r#"
+{"name":"a","vers":"1.0.0","deps":[{"name":"b","req":"^1.0","features":["std"],"optional":false,"kind":"normal"},{"name":"t","req":"*","optional":false,"kind":"dev"}],"features":{}}
+{"name":"b","vers":"1.0.0","deps":[{"name":"log","req":"0.4","features":[],"optional":true,"package":"c"}],"features":{"std":["dep:log","log/color"]}}
+{"name":"b","vers":"1.1.0-alpha","deps":[],"features":{}}
+{"name":"c","vers":"0.4.2","deps":[],"features":{"color":[],"default":["color"]},"yanked":false}
+{"name":"c","vers":"0.4.3","deps":[],"features":{"color":[]},"yanked":true}
+"#;
+
+    #[test]
+    fn import_cargo_registry() {
+        let json = import_registry(REGISTRY).unwrap();
+        let b = &json["packages"]["b"];
+        assert!(b.get("1.1.0-alpha").is_none());
+        assert_eq!(
+            b["1.0.0"]["features"]["std"]["log"],
+            json!({"package": "c", "req": "0.4", "features": ["color"]})
+        );
+        assert_eq!(
+            json["packages"]["c"]["0.4.2"]["features"]["default"]["c"],
+            json!({"package": "c", "req": "=0.4.2", "features": ["color"]})
+        );
+        let index = Index::from_json(&json).unwrap();
+        let root = Package::from_str("a#1").unwrap();
+        let solution = pubgrub::solver::resolve(&index, root, (1, 0, 0)).unwrap();
+        let mut expected = Lock::new();
+        expected.add("a", (1, 0, 0));
+        expected.add("b", (1, 0, 0));
+        expected.add("c", (0, 4, 2));
+        assert_eq!(Lock::from_solution(&solution), expected);
+    }
+}
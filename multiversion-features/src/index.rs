@@ -1,34 +1,180 @@
 // SPDX-License-Identifier: MPL-2.0
 // https://github.com/pubgrub-rs/advanced_dependency_providers/
 
+use crate::lock::Lock;
+use crate::options::{ResolveOptions, VersionOrder};
+use core::fmt::Display;
 use core::ops::{Bound, RangeBounds};
+use itertools::Either;
 use pubgrub::range::Range;
 use pubgrub::type_aliases::Map;
 use pubgrub::version::SemanticVersion as SemVer;
 use std::collections::{BTreeMap, BTreeSet as Set};
+use std::str::FromStr;
+
+/// Each package is identified by its name and its source,
+/// so that packages with the same name from different sources can coexist.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PackageName {
+    /// The name of the package within its source.
+    pub name: String,
+    /// Where the package comes from.
+    pub source: Source,
+}
+
+/// Where a package comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Source {
+    /// The default registry.
+    Registry,
+    /// A git repository, identified by its URL.
+    Git(String),
+    /// A local directory, identified by its path.
+    Path(String),
+}
+
+impl PackageName {
+    /// A package from the default registry.
+    pub fn registry(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            source: Source::Registry,
+        }
+    }
+
+    /// A package from a git repository.
+    pub fn git(url: &str, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            source: Source::Git(url.to_string()),
+        }
+    }
+
+    /// A package from a local directory.
+    pub fn path(path: &str, name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            source: Source::Path(path.to_string()),
+        }
+    }
+}
+
+/// Bare names are registry packages.
+impl From<&str> for PackageName {
+    fn from(name: &str) -> Self {
+        Self::registry(name)
+    }
+}
+
+impl FromStr for PackageName {
+    type Err = String;
+    /// "a" -> registry package, "a(git+url)" -> git package, "a(path+dir)" -> path package
+    fn from_str(pkg: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{} is not a valid package name", pkg);
+        let (name, source) = match pkg.split_once('(') {
+            None => (pkg, Source::Registry),
+            Some((name, source)) => {
+                let source = source.strip_suffix(')').ok_or_else(invalid)?;
+                if let Some(url) = source.strip_prefix("git+") {
+                    (name, Source::Git(url.to_string()))
+                } else if let Some(path) = source.strip_prefix("path+") {
+                    (name, Source::Path(path.to_string()))
+                } else {
+                    return Err(invalid());
+                }
+            }
+        };
+        if name.is_empty() || name.contains(['#', '/', ')']) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            source,
+        })
+    }
+}
+
+impl Display for PackageName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.source {
+            Source::Registry => write!(f, "{}", self.name),
+            Source::Git(url) => write!(f, "{}(git+{})", self.name, url),
+            Source::Path(path) => write!(f, "{}(path+{})", self.name, path),
+        }
+    }
+}
 
-/// Each package is identified by its name.
-pub type PackageName = String;
 /// Features are identified by their name.
 pub type Feature = String;
 
 /// Global registry of known packages.
+#[derive(Clone)]
 pub struct Index {
     /// Specify dependencies of each package version.
     pub packages: Map<PackageName, BTreeMap<SemVer, Deps>>,
+    /// Versions pinned by a previous resolution.
+    /// Yanked versions are only available if they are locked.
+    pub lock: Lock,
+    /// Packages that must appear at most once across the whole graph.
+    pub singletons: Set<PackageName>,
+    /// Native library linked by a package, like Cargo `links`.
+    /// Packages sharing a `links` value must appear at most once across the whole graph.
+    pub links: Map<PackageName, String>,
+    /// Options tuning the resolution.
+    pub options: ResolveOptions,
+    /// Duplication policy of each package, overriding the default policy.
+    pub policies: Map<PackageName, Policy>,
+    /// Duplication policy of packages without a specific policy.
+    pub default_policy: Policy,
+}
+
+/// Whether multiple versions of a package may be selected together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// One version may be selected per bucket of versions sharing their major component.
+    Multiple,
+    /// Only one version of the package may be selected.
+    Single,
 }
 
 /// Dependencies include mandatory dependencies and optional dependencies.
 /// Optional dependencies are identified by an option called a "feature".
+/// Dependencies are keyed by the local name under which they are used.
+#[derive(Clone)]
 pub struct Deps {
     /// The regular, mandatory dependencies.
-    pub mandatory: Map<PackageName, Dep>,
+    pub mandatory: Map<String, Dep>,
     /// The optional, feature-gated dependencies.
-    pub optional: Map<Feature, Map<PackageName, Dep>>,
+    pub optional: Map<Feature, Map<String, Dep>>,
+    /// Whether this version is still available for new resolutions.
+    pub status: Status,
+}
+
+/// Publication status of a package version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The version can be picked by any resolution.
+    Available,
+    /// The version was yanked from the registry.
+    Yanked,
+    /// The version was retracted by its authors.
+    Retracted,
+}
+
+impl Status {
+    /// Yanked and retracted versions are only picked when locked.
+    pub fn is_yanked(&self) -> bool {
+        self != &Status::Available
+    }
 }
 
 /// A dependency is specified with a range, and with a set of activated features.
+/// Dependencies are keyed by the name under which they are used, which usually is the name
+/// of the package depended upon, but may differ for renamed dependencies.
+#[derive(Clone)]
 pub struct Dep {
+    /// The package depended upon.
+    pub package: PackageName,
     /// The range dependended upon.
     pub range: Range<SemVer>,
     /// The activated features for that dependency.
@@ -40,6 +186,7 @@ impl Default for Deps {
         Self {
             mandatory: Map::default(),
             optional: Map::default(),
+            status: Status::Available,
         }
     }
 }
@@ -49,33 +196,142 @@ impl Index {
     pub fn new() -> Self {
         Self {
             packages: Map::default(),
+            lock: Lock::default(),
+            singletons: Set::new(),
+            links: Map::default(),
+            options: ResolveOptions::default(),
+            policies: Map::default(),
+            default_policy: Policy::Multiple,
         }
     }
 
-    /// List existing versions for a given package with newest versions first.
+    /// Set the duplication policy of a package.
+    pub fn set_policy(&mut self, package: impl Into<PackageName>, policy: Policy) {
+        self.policies.insert(package.into(), policy);
+    }
+
+    /// Duplication policy of a package.
+    pub fn policy(&self, package: &PackageName) -> Policy {
+        self.policies
+            .get(package)
+            .copied()
+            .unwrap_or(self.default_policy)
+    }
+
+    /// Forbid multiple versions of a package in the same resolution.
+    pub fn add_singleton(&mut self, package: impl Into<PackageName>) {
+        self.singletons.insert(package.into());
+    }
+
+    /// Declare the native library linked by a package.
+    pub fn set_links(&mut self, package: impl Into<PackageName>, links: &str) {
+        self.links.insert(package.into(), links.to_string());
+    }
+
+    /// Packages linking the given native library, sorted by name.
+    pub fn linking_packages(&self, links: &str) -> Vec<&PackageName> {
+        let mut packages: Vec<_> = self
+            .links
+            .iter()
+            .filter(|(_, l)| l.as_str() == links)
+            .map(|(p, _)| p)
+            .collect();
+        packages.sort();
+        packages
+    }
+
+    /// List existing versions for a given package in the order they should be tried,
+    /// newest versions first unless another version order is set.
+    /// Yanked versions are skipped, unless they are locked.
     pub fn available_versions(&self, package: &PackageName) -> impl Iterator<Item = &SemVer> {
+        let locked = self.lock.packages.get(package);
+        let versions = self
+            .packages
+            .get(package)
+            .into_iter()
+            .flat_map(|k| k.iter());
+        let versions = if self.oldest_first(package) {
+            Either::Left(versions)
+        } else {
+            Either::Right(versions.rev())
+        };
+        versions
+            .filter(move |(v, deps)| {
+                !deps.status.is_yanked() || locked.is_some_and(|l| l.contains(*v))
+            })
+            .map(|(v, _)| v)
+    }
+
+    /// Whether the oldest versions of a package should be tried first.
+    fn oldest_first(&self, package: &PackageName) -> bool {
+        match &self.options.version_order {
+            VersionOrder::Newest => false,
+            VersionOrder::Oldest => true,
+            VersionOrder::OldestDirect(direct) => direct.contains(package),
+        }
+    }
+
+    /// Packages depended upon by any version of the roots, mandatory or optional,
+    /// to try their oldest versions first with [VersionOrder::OldestDirect].
+    pub fn direct_dependencies<'r>(
+        &self,
+        roots: impl IntoIterator<Item = &'r PackageName>,
+    ) -> Set<PackageName> {
+        roots
+            .into_iter()
+            .filter_map(|root| self.packages.get(root))
+            .flat_map(|versions| versions.values())
+            .flat_map(|deps| {
+                let optional = deps.optional.values().flat_map(|deps| deps.values());
+                deps.mandatory.values().chain(optional)
+            })
+            .map(|dep| dep.package.clone())
+            .collect()
+    }
+
+    /// List yanked versions for a given package with newest versions first.
+    pub fn yanked_versions(&self, package: &PackageName) -> impl Iterator<Item = &SemVer> {
         self.packages
             .get(package)
             .into_iter()
-            .flat_map(|k| k.keys())
+            .flat_map(|k| k.iter())
             .rev()
+            .filter(|(_, deps)| deps.status.is_yanked())
+            .map(|(v, _)| v)
+    }
+
+    /// Change the status of a registered package version.
+    pub fn set_status(
+        &mut self,
+        package: impl Into<PackageName>,
+        version: (u32, u32, u32),
+        status: Status,
+    ) {
+        if let Some(deps) = self
+            .packages
+            .get_mut(&package.into())
+            .and_then(|versions| versions.get_mut(&version.into()))
+        {
+            deps.status = status;
+        }
     }
 
     /// Register a package and its mandatory dependencies in the index.
     pub fn add_deps<R: RangeBounds<(u32, u32, u32)>>(
         &mut self,
-        package: &str,
+        package: impl Into<PackageName>,
         version: (u32, u32, u32),
         mandatory_deps: &[(&str, R, &[&str])],
     ) {
         let deps = self
             .packages
-            .entry(package.to_string())
+            .entry(package.into())
             .or_default()
             .entry(version.into())
             .or_default();
         for (p, r, features) in mandatory_deps {
             let dep = Dep {
+                package: PackageName::registry(p),
                 range: range_from_bounds(r),
                 features: features.iter().map(|s| s.to_string()).collect(),
             };
@@ -83,17 +339,43 @@ impl Index {
         }
     }
 
+    /// Register a package and its mandatory dependencies in the index,
+    /// with dependencies used under a local name different from the package name,
+    /// like Cargo `package = "..."` or npm `npm:` aliases,
+    /// or on packages from another source.
+    pub fn add_renamed_deps<P: Clone + Into<PackageName>, R: RangeBounds<(u32, u32, u32)>>(
+        &mut self,
+        package: impl Into<PackageName>,
+        version: (u32, u32, u32),
+        renamed_deps: &[(&str, P, R, &[&str])],
+    ) {
+        let deps = self
+            .packages
+            .entry(package.into())
+            .or_default()
+            .entry(version.into())
+            .or_default();
+        for (alias, p, r, features) in renamed_deps {
+            let dep = Dep {
+                package: p.clone().into(),
+                range: range_from_bounds(r),
+                features: features.iter().map(|s| s.to_string()).collect(),
+            };
+            deps.mandatory.insert(String::from(*alias), dep);
+        }
+    }
+
     /// Register a feature and its associated dependencies for a given package in the index.
     pub fn add_feature<R: RangeBounds<(u32, u32, u32)>>(
         &mut self,
-        package: &str,
+        package: impl Into<PackageName>,
         version: (u32, u32, u32),
         feature: &str,
         optional_deps: &[(&str, R, &[&str])],
     ) {
         let deps = self
             .packages
-            .entry(package.to_string())
+            .entry(package.into())
             .or_default()
             .entry(version.into())
             .or_default()
@@ -102,6 +384,7 @@ impl Index {
             .or_default();
         for (p, r, features) in optional_deps {
             let dep = Dep {
+                package: PackageName::registry(p),
                 range: range_from_bounds(r),
                 features: features.iter().map(|s| s.to_string()).collect(),
             };
@@ -111,7 +394,7 @@ impl Index {
 }
 
 /// Convert a range bounds into pubgrub Range type.
-fn range_from_bounds<R: RangeBounds<(u32, u32, u32)>>(bounds: &R) -> Range<SemVer> {
+pub(crate) fn range_from_bounds<R: RangeBounds<(u32, u32, u32)>>(bounds: &R) -> Range<SemVer> {
     match (bounds.start_bound(), bounds.end_bound()) {
         (Bound::Unbounded, Bound::Unbounded) => Range::any(),
         (Bound::Unbounded, Bound::Excluded(end)) => Range::strictly_lower_than(*end),
@@ -147,4 +430,38 @@ pub mod tests {
         index.add_feature("d", (1, 0, 0), "feat", &[("f", (1, 0, 0).., &[])]);
         index.add_deps::<R>("f", (1, 0, 0), &[]);
     }
+
+    #[test]
+    fn yanked_versions_need_a_lock() {
+        let mut index = Index::new();
+        index.add_deps::<R>("a", (1, 0, 0), &[]);
+        index.add_deps::<R>("a", (1, 1, 0), &[]);
+        index.add_deps::<R>("a", (1, 2, 0), &[]);
+        index.set_status("a", (1, 1, 0), Status::Yanked);
+        index.set_status("a", (1, 2, 0), Status::Retracted);
+        let a = PackageName::from("a");
+        let available: Vec<_> = index.available_versions(&a).cloned().collect();
+        assert_eq!(available, vec![(1, 0, 0).into()]);
+        index.lock.add("a", (1, 1, 0));
+        let available: Vec<_> = index.available_versions(&a).cloned().collect();
+        assert_eq!(available, vec![(1, 1, 0).into(), (1, 0, 0).into()]);
+    }
+
+    #[test]
+    fn package_names_with_sources() {
+        for name in [
+            "foo",
+            "foo(git+https://example.com/foo.git)",
+            "foo(path+../foo)",
+        ] {
+            assert_eq!(PackageName::from_str(name).unwrap().to_string(), name);
+        }
+        assert_eq!(
+            PackageName::from_str("foo(path+vendor/foo)"),
+            Ok(PackageName::path("vendor/foo", "foo"))
+        );
+        assert!(PackageName::from_str("foo(svn+url)").is_err());
+        assert!(PackageName::from_str("foo(git+url").is_err());
+        assert!(PackageName::from_str("").is_err());
+    }
 }
@@ -0,0 +1,367 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{Dep, Deps, Index, PackageName, Policy, Status};
+use crate::lock::Lock;
+use pubgrub::range::Range;
+use pubgrub::version::SemanticVersion as SemVer;
+use serde_json::{json, Map as JsonMap, Value};
+use std::str::FromStr;
+
+impl Index {
+    /// Load an index from its JSON file format.
+    ///
+    /// The "packages" object maps package names, like "a" or "a(git+url)", to an object
+    /// mapping versions to their "dependencies", their "features", and their "status"
+    /// ("available", "yanked" or "retracted").
+    /// Dependencies and the dependencies of each feature map local names to a requirement,
+    /// either a string like "^1.2" on the package of that name, or an object with
+    /// an optional "package", "req" and "features".
+    /// Optional top-level fields are "singletons", "links", "policies" ("single" or "multiple"),
+    /// "default_policy", "max_versions" and "lock".
+    pub fn from_json(json: &Value) -> Result<Self, String> {
+        let mut index = Index::new();
+        for (name, versions) in object(json.get("packages"), "packages")? {
+            let package = PackageName::from_str(name)?;
+            let versions = object(Some(versions), name)?;
+            let registered = index.packages.entry(package).or_default();
+            for (version, deps) in versions {
+                let what = format!("{} {}", name, version);
+                let version = SemVer::from_str(version).map_err(|e| e.to_string())?;
+                registered.insert(version, deps_from_json(deps, &what)?);
+            }
+        }
+        if let Some(singletons) = json.get("singletons") {
+            for name in array(singletons, "singletons")? {
+                index.add_singleton(PackageName::from_str(string(name, "singletons")?)?);
+            }
+        }
+        for (name, links) in object(json.get("links"), "links")? {
+            index.set_links(PackageName::from_str(name)?, string(links, name)?);
+        }
+        for (name, policy) in object(json.get("policies"), "policies")? {
+            index.set_policy(PackageName::from_str(name)?, policy_from_json(policy)?);
+        }
+        if let Some(policy) = json.get("default_policy") {
+            index.default_policy = policy_from_json(policy)?;
+        }
+        if let Some(max) = json.get("max_versions") {
+            let max = max.as_u64().ok_or("max_versions must be a number")?;
+            index.options.max_versions = Some(max as u32);
+        }
+        if let Some(lock) = json.get("lock") {
+            index.lock = Lock::from_json(lock)?;
+        }
+        Ok(index)
+    }
+}
+
+impl Lock {
+    /// Load a lock from JSON, an object mapping package names to their locked versions.
+    pub fn from_json(json: &Value) -> Result<Self, String> {
+        let mut lock = Lock::new();
+        for (name, versions) in object(Some(json), "lock")? {
+            let locked = lock
+                .packages
+                .entry(PackageName::from_str(name)?)
+                .or_default();
+            for version in array(versions, name)? {
+                let version =
+                    SemVer::from_str(string(version, name)?).map_err(|e| e.to_string())?;
+                locked.insert(version);
+            }
+        }
+        Ok(lock)
+    }
+
+    /// JSON serialization of the lock, readable by `Lock::from_json`.
+    pub fn to_json(&self) -> Value {
+        let packages: JsonMap<String, Value> = self
+            .packages
+            .iter()
+            .map(|(name, versions)| {
+                let versions: Vec<String> = versions.iter().map(|v| v.to_string()).collect();
+                (name.to_string(), json!(versions))
+            })
+            .collect();
+        Value::Object(packages)
+    }
+}
+
+/// Parse a version requirement with the syntax of Cargo, like "^1.2", "~1.2.3", ">=1, <3",
+/// "=1.0.0", "1.*" or "*".
+/// A bare version is a caret requirement, and alternatives may be joined with "||".
+/// Pre-release and build metadata are ignored.
+pub fn parse_requirement(req: &str) -> Result<Range<SemVer>, String> {
+    let mut range = Range::none();
+    for alternative in req.split("||") {
+        let mut intersection = Range::any();
+        for comparator in alternative.split(',') {
+            intersection = intersection.intersection(&parse_comparator(comparator.trim())?);
+        }
+        range = range.union(&intersection);
+    }
+    Ok(range)
+}
+
+/// Parse a dependency written NAME[@REQ][:FEATURE,...], like "a@^1.2:std,serde",
+/// on any version of the package when the requirement is missing.
+pub fn parse_dep(spec: &str) -> Result<Dep, String> {
+    // Names may contain a source between parentheses, like "a(git+url)".
+    let name_end = spec.find(')').unwrap_or(0);
+    let (name, rest) = match spec[name_end..].find(['@', ':']) {
+        Some(i) => spec.split_at(name_end + i),
+        None => (spec, ""),
+    };
+    let (req, features) = match rest.split_once(':') {
+        Some((req, features)) => (req, features),
+        None => (rest, ""),
+    };
+    let range = match req.strip_prefix('@') {
+        Some(req) => parse_requirement(req)?,
+        None => Range::any(),
+    };
+    Ok(Dep {
+        package: PackageName::from_str(name)?,
+        range,
+        features: features
+            .split(',')
+            .filter(|f| !f.is_empty())
+            .map(|f| f.to_string())
+            .collect(),
+    })
+}
+
+fn parse_comparator(comparator: &str) -> Result<Range<SemVer>, String> {
+    if matches!(comparator, "*" | "x" | "X") {
+        return Ok(Range::any());
+    }
+    let start = comparator
+        .find(|c: char| c.is_ascii_digit())
+        .ok_or_else(|| format!("{} is not a valid requirement", comparator))?;
+    let (op, version) = comparator.split_at(start);
+    let version = Partial::from_str(version)?;
+    Ok(match op.trim() {
+        "" | "^" => Range::between(version.lowest(), version.caret_upper()),
+        "~" => Range::between(version.lowest(), version.tilde_upper()),
+        "=" => Range::between(version.lowest(), version.upper()),
+        ">=" => Range::higher_than(version.lowest()),
+        ">" => Range::higher_than(version.upper()),
+        "<" => Range::strictly_lower_than(version.lowest()),
+        "<=" => Range::strictly_lower_than(version.upper()),
+        op => return Err(format!("unknown operator {} in {}", op, comparator)),
+    })
+}
+
+/// A version with possibly missing minor and patch components, like "1" or "1.2.*".
+struct Partial {
+    major: u32,
+    minor: Option<u32>,
+    patch: Option<u32>,
+}
+
+impl FromStr for Partial {
+    type Err = String;
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{} is not a valid version", version);
+        let release = version.split(['-', '+']).next().unwrap_or(version);
+        let components = release
+            .split('.')
+            .map(|c| match c {
+                "*" | "x" | "X" => Ok(None),
+                c => c.parse().map(Some).map_err(|_| invalid()),
+            })
+            .collect::<Result<Vec<Option<u32>>, String>>()?;
+        match components[..] {
+            [Some(major)] => Ok(Self {
+                major,
+                minor: None,
+                patch: None,
+            }),
+            [Some(major), minor] | [Some(major), minor, None] => Ok(Self {
+                major,
+                minor,
+                patch: None,
+            }),
+            [Some(major), Some(minor), patch] => Ok(Self {
+                major,
+                minor: Some(minor),
+                patch,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Partial {
+    /// The lowest version matching the partial version.
+    fn lowest(&self) -> SemVer {
+        SemVer::new(self.major, self.minor.unwrap_or(0), self.patch.unwrap_or(0))
+    }
+
+    /// The first version above all versions matching the partial version.
+    fn upper(&self) -> SemVer {
+        match (self.minor, self.patch) {
+            (Some(minor), Some(patch)) => SemVer::new(self.major, minor, patch + 1),
+            (Some(minor), None) => SemVer::new(self.major, minor + 1, 0),
+            _ => SemVer::new(self.major + 1, 0, 0),
+        }
+    }
+
+    /// The first version incompatible with the partial version, changing
+    /// the leftmost non-zero component.
+    fn caret_upper(&self) -> SemVer {
+        match (self.major, self.minor, self.patch) {
+            (0, Some(0), Some(patch)) => SemVer::new(0, 0, patch + 1),
+            (0, Some(minor), _) => SemVer::new(0, minor + 1, 0),
+            (major, _, _) => SemVer::new(major + 1, 0, 0),
+        }
+    }
+
+    /// The first version with another minor component, or another major one without minor.
+    fn tilde_upper(&self) -> SemVer {
+        match self.minor {
+            Some(minor) => SemVer::new(self.major, minor + 1, 0),
+            None => SemVer::new(self.major + 1, 0, 0),
+        }
+    }
+}
+
+fn deps_from_json(json: &Value, what: &str) -> Result<Deps, String> {
+    let mut deps = Deps::default();
+    for (alias, dep) in object(json.get("dependencies"), what)? {
+        deps.mandatory
+            .insert(alias.clone(), dep_from_json(alias, dep)?);
+    }
+    for (feature, feature_deps) in object(json.get("features"), what)? {
+        let optional = deps.optional.entry(feature.clone()).or_default();
+        for (alias, dep) in object(Some(feature_deps), feature)? {
+            optional.insert(alias.clone(), dep_from_json(alias, dep)?);
+        }
+    }
+    deps.status = match json.get("status").map(|s| string(s, what)).transpose()? {
+        None | Some("available") => Status::Available,
+        Some("yanked") => Status::Yanked,
+        Some("retracted") => Status::Retracted,
+        Some(status) => return Err(format!("unknown status {} of {}", status, what)),
+    };
+    Ok(deps)
+}
+
+fn dep_from_json(alias: &str, json: &Value) -> Result<Dep, String> {
+    if let Value::String(req) = json {
+        return Ok(Dep {
+            package: PackageName::from_str(alias)?,
+            range: parse_requirement(req)?,
+            features: Default::default(),
+        });
+    }
+    let package = match json.get("package") {
+        Some(package) => string(package, alias)?,
+        None => alias,
+    };
+    let range = match json.get("req") {
+        Some(req) => parse_requirement(string(req, alias)?)?,
+        None => Range::any(),
+    };
+    let mut features = std::collections::BTreeSet::new();
+    if let Some(list) = json.get("features") {
+        for feature in array(list, alias)? {
+            features.insert(string(feature, alias)?.to_string());
+        }
+    }
+    Ok(Dep {
+        package: PackageName::from_str(package)?,
+        range,
+        features,
+    })
+}
+
+fn policy_from_json(json: &Value) -> Result<Policy, String> {
+    match string(json, "policy")? {
+        "multiple" => Ok(Policy::Multiple),
+        "single" => Ok(Policy::Single),
+        policy => Err(format!("unknown policy {}", policy)),
+    }
+}
+
+/// Entries of an optional JSON object, a missing object having none.
+fn object<'j>(
+    json: Option<&'j Value>,
+    what: &str,
+) -> Result<impl Iterator<Item = (&'j String, &'j Value)>, String> {
+    let object = json
+        .map(|json| {
+            json.as_object()
+                .ok_or_else(|| format!("{} must be an object", what))
+        })
+        .transpose()?;
+    Ok(object.into_iter().flatten())
+}
+
+fn array<'j>(json: &'j Value, what: &str) -> Result<&'j Vec<Value>, String> {
+    json.as_array()
+        .ok_or_else(|| format!("{} must be an array", what))
+}
+
+fn string<'j>(json: &'j Value, what: &str) -> Result<&'j str, String> {
+    json.as_str()
+        .ok_or_else(|| format!("{} must be a string", what))
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::multiversion_optional_deps::Package;
+
+    #[test]
+    fn requirements() {
+        let cases = [
+            ("^1.2", "1.2.0 <= v < 2.0.0"),
+            ("0.3", "0.3.0 <= v < 0.4.0"),
+            ("^0.0.3", "0.0.3"),
+            ("~1.2.3", "1.2.3 <= v < 1.3.0"),
+            ("=1.2", "1.2.0 <= v < 1.3.0"),
+            ("1.*", "1.0.0 <= v < 2.0.0"),
+            (">=1, <3", "1.0.0 <= v < 3.0.0"),
+            ("<=1.4", "v < 1.5.0"),
+            ("*", "∗"),
+        ];
+        for (req, range) in cases {
+            assert_eq!(
+                parse_requirement(req).unwrap().to_string(),
+                range,
+                "{}",
+                req
+            );
+        }
+        assert!(parse_requirement("1.x.2").is_err());
+        assert!(parse_requirement("!1").is_err());
+    }
+
+    #[test]
+    fn load_index_and_lock() {
+        let json = json!({
+            "packages": {
+                "a": {"1.0.0": {"dependencies": {"b": "^1", "renamed": {"package": "c", "features": ["x"]}}}},
+                "b": {"1.0.0": {}, "1.1.0": {"status": "yanked"}},
+                "c": {"2.0.0": {"features": {"x": {"b": ">=1.0.0, <1.1.0"}}}},
+            },
+            "lock": {"b": ["1.1.0"]},
+        });
+        let index = Index::from_json(&json).unwrap();
+        assert_eq!(
+            Lock::from_json(&index.lock.to_json()),
+            Ok(index.lock.clone())
+        );
+        let root = Package::from_str("a#1").unwrap();
+        let solution = pubgrub::solver::resolve(&index, root, (1, 0, 0)).unwrap();
+        let mut expected = Lock::new();
+        expected.add("a", (1, 0, 0));
+        expected.add("b", (1, 0, 0));
+        expected.add("c", (2, 0, 0));
+        assert_eq!(Lock::from_solution(&solution), expected);
+        assert!(Index::from_json(&json!({"packages": {"a": {"1": {}}}})).is_err());
+    }
+}
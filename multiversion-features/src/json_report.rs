@@ -0,0 +1,307 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::Index;
+use crate::multiversion_optional_deps::Package;
+use crate::reporter::{collapse_proxies, UserReporter, UserTerms};
+use crate::workspace::{is_workspace_root, requirement_package};
+use pubgrub::range::Range;
+use pubgrub::report::{DerivationTree, External, Reporter};
+use pubgrub::term::Term;
+use pubgrub::version::SemanticVersion as SemVer;
+use serde_json::{json, Value};
+
+/// Reporter serializing a failure as JSON, for tools consuming resolution failures.
+/// Packages are translated like the [UserReporter] does, and proxies are collapsed.
+///
+/// The report has the explanation text in "message", the derivation tree in "tree",
+/// and the distinct external reasons of the failure in "root_causes".
+/// A node of the tree is either an external incompatibility,
+/// `{"kind": "external", "reason", "message", "terms"}`,
+/// or one derived from its two causes,
+/// `{"kind": "derived", "id", "message", "terms", "causes"}`.
+/// Terms are `{"package", "range", "positive"}`,
+/// where packages are `{"name"}` with an optional "feature",
+/// the "dependent" package and the "alias" it depends upon behind a proxy,
+/// or the "limit" enforced by a marker.
+/// The name behind a proxy is the one of the package it stands for when it is known,
+/// which [JsonReporter::report_for_index] ensures.
+/// The workspace root is always selected, so it has no terms,
+/// and the packages it asks for have no "dependent".
+pub struct JsonReporter;
+
+impl Reporter<Package, SemVer> for JsonReporter {
+    type Output = Value;
+
+    fn report(derivation_tree: &DerivationTree<Package, SemVer>) -> Self::Output {
+        let message = UserReporter::report(derivation_tree);
+        Self::report_with(&UserTerms::new(derivation_tree), message, derivation_tree)
+    }
+}
+
+impl JsonReporter {
+    /// Report with the packages behind proxies looked up in the index,
+    /// even those whose dependencies are not part of the derivation tree.
+    pub fn report_for_index(
+        index: &Index,
+        derivation_tree: &DerivationTree<Package, SemVer>,
+    ) -> Value {
+        let message = UserReporter::report_for_index(index, derivation_tree);
+        let user = UserTerms::for_index(index, derivation_tree);
+        Self::report_with(&user, message, derivation_tree)
+    }
+
+    fn report_with(
+        user: &UserTerms,
+        message: String,
+        derivation_tree: &DerivationTree<Package, SemVer>,
+    ) -> Value {
+        let mut root_causes = Vec::new();
+        let tree = node(
+            user,
+            &collapse_proxies(derivation_tree.clone()),
+            &mut root_causes,
+        );
+        json!({
+            "message": message,
+            "tree": tree,
+            "root_causes": root_causes,
+        })
+    }
+}
+
+/// A node of the derivation tree, collecting the external reasons found below it.
+fn node(
+    user: &UserTerms,
+    tree: &DerivationTree<Package, SemVer>,
+    root_causes: &mut Vec<Value>,
+) -> Value {
+    match tree {
+        DerivationTree::External(external) => {
+            let value = self::external(user, external);
+            if !matches!(external, External::NotRoot(..)) && !root_causes.contains(&value) {
+                root_causes.push(value.clone());
+            }
+            value
+        }
+        DerivationTree::Derived(derived) => {
+            let mut terms: Vec<_> = derived.terms.iter().collect();
+            terms.sort_by_key(|(package, _)| package.to_string());
+            let terms: Vec<_> = terms
+                .into_iter()
+                .filter_map(|(package, t)| match t {
+                    Term::Positive(range) => term(user, package, range, true),
+                    Term::Negative(range) => term(user, package, range, false),
+                })
+                .collect();
+            json!({
+                "kind": "derived",
+                "id": derived.shared_id,
+                "message": user.terms(&derived.terms),
+                "terms": terms,
+                "causes": [
+                    node(user, &derived.cause1, root_causes),
+                    node(user, &derived.cause2, root_causes),
+                ],
+            })
+        }
+    }
+}
+
+/// An external incompatibility, with its reason and the terms it forbids together.
+fn external(user: &UserTerms, external: &External<Package, SemVer>) -> Value {
+    let (reason, terms) = match external {
+        External::NotRoot(package, version) => (
+            "not_root",
+            vec![term(user, package, &Range::exact(*version), false)],
+        ),
+        External::NoVersions(package, range) => {
+            ("no_versions", vec![term(user, package, range, true)])
+        }
+        External::UnavailableDependencies(package, range) => (
+            "unavailable_dependencies",
+            vec![term(user, package, range, true)],
+        ),
+        External::FromDependencyOf(package, range, dep, dep_range) => (
+            "dependency",
+            vec![
+                term(user, package, range, true),
+                term(user, dep, dep_range, false),
+            ],
+        ),
+    };
+    json!({
+        "kind": "external",
+        "reason": reason,
+        "message": user.external(external),
+        "terms": terms.into_iter().flatten().collect::<Vec<_>>(),
+    })
+}
+
+/// A term, or None for the workspace root.
+fn term(
+    user: &UserTerms,
+    package: &Package,
+    range: &Range<SemVer>,
+    positive: bool,
+) -> Option<Value> {
+    if matches!(package, Package::Bucket(_)) && is_workspace_root(package) {
+        return None;
+    }
+    Some(json!({
+        "package": self::package(user, package),
+        "range": user.range(package, range).to_string(),
+        "positive": positive,
+    }))
+}
+
+fn package(user: &UserTerms, package: &Package) -> Value {
+    match package {
+        Package::Proxy {
+            target, feature, ..
+        } if is_workspace_root(package) => json!({
+            "name": requirement_package(target),
+            "feature": feature,
+        }),
+        Package::Bucket(bucket) => json!({ "name": bucket.name.to_string() }),
+        Package::Feature { base, feature } => json!({
+            "name": base.name.to_string(),
+            "feature": feature,
+        }),
+        Package::Proxy {
+            source,
+            target,
+            feature,
+            ..
+        } => json!({
+            "name": user
+                .proxy_package(package)
+                .map_or_else(|| target.clone(), |name| name.to_string()),
+            "alias": target,
+            "feature": feature,
+            "dependent": {
+                "name": source.0.name.to_string(),
+                "version": source.1.to_string(),
+            },
+        }),
+        Package::Singleton(name) => json!({
+            "name": name.to_string(),
+            "limit": "singleton",
+        }),
+        Package::Links(links) => json!({ "links": links, "limit": "links" }),
+        Package::SlotOf(bucket) => json!({
+            "name": bucket.name.to_string(),
+            "limit": "slots",
+        }),
+        Package::Slot { name, slot } => json!({
+            "name": name.to_string(),
+            "limit": "slots",
+            "slot": slot,
+        }),
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use pubgrub::error::PubGrubError;
+    use std::str::FromStr;
+
+    fn failure(index: &Index) -> DerivationTree<Package, SemVer> {
+        let root = Package::from_str("a#1").unwrap();
+        match pubgrub::solver::resolve(index, root, (1, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => tree,
+            _ => panic!("expected no solution"),
+        }
+    }
+
+    #[test]
+    fn missing_versions_are_root_causes() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (2, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps::<core::ops::RangeFull>("b", (1, 0, 0), &[]);
+        let tree = failure(&index);
+        let report = JsonReporter::report(&tree);
+        assert_eq!(report["message"], UserReporter::report(&tree));
+        let root_causes = report["root_causes"].as_array().unwrap();
+        let no_versions = root_causes
+            .iter()
+            .find(|cause| cause["reason"] == "no_versions")
+            .unwrap();
+        assert_eq!(no_versions["terms"][0]["package"], json!({ "name": "b" }));
+        assert_eq!(no_versions["terms"][0]["positive"], true);
+    }
+
+    #[test]
+    fn proxies_and_features_are_translated() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(3, 0, 0), &["alpha"])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (3, 0, 0)..(4, 0, 0), &[])]);
+        index.add_feature::<core::ops::RangeFull>("d", (1, 0, 0), "alpha", &[]);
+        index.add_feature::<core::ops::RangeFull>("d", (2, 0, 0), "alpha", &[]);
+        index.add_deps::<core::ops::RangeFull>("d", (3, 0, 0), &[]);
+        index.add_singleton("d");
+        let report = JsonReporter::report(&failure(&index));
+        assert_eq!(report["tree"]["kind"], "derived");
+        let serialized = report.to_string();
+        assert!(!serialized.contains("->"));
+        assert!(!serialized.contains('#'));
+        let alpha = json!({ "name": "d", "feature": "alpha" });
+        assert!(report["root_causes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|cause| cause["reason"] == "dependency" && cause["terms"][1]["package"] == alpha));
+    }
+
+    #[test]
+    fn renamed_dependencies_are_named_by_package() {
+        let mut index = Index::new();
+        index.add_renamed_deps(
+            "a",
+            (1, 0, 0),
+            &[("foo_v1", "foo", (1, 0, 0)..(2, 0, 0), &[])],
+        );
+        index.add_deps::<core::ops::RangeFull>("foo", (3, 0, 0), &[]);
+        let report = JsonReporter::report_for_index(&index, &failure(&index));
+        let no_versions = report["root_causes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|cause| cause["reason"] == "no_versions")
+            .unwrap();
+        let foo = &no_versions["terms"][0]["package"];
+        assert_eq!(foo["name"], "foo");
+        assert_eq!(foo["alias"], "foo_v1");
+        assert_eq!(foo["dependent"]["name"], "a");
+        assert_eq!(no_versions["terms"][0]["range"], "1.0.0 <= v < 2.0.0");
+    }
+
+    #[test]
+    fn workspace_root_is_left_out() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (2, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps::<core::ops::RangeFull>("b", (1, 0, 0), &[]);
+        let (index, root) = index.with_workspace_root([crate::index::Dep {
+            package: "a".into(),
+            range: Range::any(),
+            features: Default::default(),
+        }]);
+        let tree = match pubgrub::solver::resolve(&index, root, (0, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => tree,
+            _ => panic!("expected no solution"),
+        };
+        let report = JsonReporter::report(&tree);
+        let serialized = report.to_string();
+        assert!(!serialized.contains(crate::workspace::WORKSPACE_ROOT));
+        assert!(!serialized.contains("a[0]"));
+        assert!(report["root_causes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|cause| cause["message"] == "the requirements ask for a 1.0.0 <= v < 2.0.0"));
+    }
+}
@@ -1,2 +1,21 @@
+pub mod diff;
+pub mod dot;
+pub mod hypergraph;
+pub mod import;
 pub mod index;
+pub mod index_file;
+pub mod json_report;
+pub mod lock;
+pub mod minimize;
 pub mod multiversion_optional_deps;
+pub mod options;
+pub mod overrides;
+pub mod relax;
+pub mod repl;
+pub mod report;
+pub mod reporter;
+pub mod resolution;
+pub mod tree;
+pub mod update;
+pub mod why;
+pub mod workspace;
@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::PackageName;
+use crate::multiversion_optional_deps::Package;
+use pubgrub::type_aliases::{Map, SelectedDependencies};
+use pubgrub::version::SemanticVersion as SemVer;
+use std::collections::BTreeSet as Set;
+
+/// Versions pinned by a previous resolution, like a lockfile.
+/// Since multiple versions of a package may coexist,
+/// there may be one locked version per bucket.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lock {
+    /// Locked versions of each package.
+    pub packages: Map<PackageName, Set<SemVer>>,
+}
+
+impl Lock {
+    /// Empty new lock.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lock a package version.
+    pub fn add(&mut self, package: impl Into<PackageName>, version: (u32, u32, u32)) {
+        self.packages
+            .entry(package.into())
+            .or_default()
+            .insert(version.into());
+    }
+
+    /// Check if a package version is locked.
+    pub fn contains(&self, package: &PackageName, version: &SemVer) -> bool {
+        self.packages
+            .get(package)
+            .is_some_and(|versions| versions.contains(version))
+    }
+
+    /// Lock all the package versions picked in a solution.
+    pub fn from_solution(solution: &SelectedDependencies<Package, SemVer>) -> Self {
+        let mut lock = Self::new();
+        for (package, version) in solution {
+            if let Package::Bucket(bucket) = package {
+                lock.packages
+                    .entry(bucket.name.clone())
+                    .or_default()
+                    .insert(*version);
+            }
+        }
+        lock
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::index::{Index, Status};
+    use pubgrub::solver::resolve;
+    use std::str::FromStr;
+
+    #[test]
+    fn lock_from_solution_keeps_buckets() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &["feat"])]);
+        index.add_feature::<core::ops::RangeFull>("b", (1, 2, 0), "feat", &[]);
+        let root = Package::from_str("a#1").unwrap();
+        let solution = resolve(&index, root, (1, 0, 0)).unwrap();
+        let lock = Lock::from_solution(&solution);
+        let mut expected = Lock::new();
+        expected.add("a", (1, 0, 0));
+        expected.add("b", (1, 2, 0));
+        assert_eq!(lock, expected);
+    }
+
+    #[test]
+    fn yanked_version_resolves_when_locked() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps::<core::ops::RangeFull>("b", (1, 0, 0), &[]);
+        index.set_status("b", (1, 0, 0), Status::Yanked);
+        let root = Package::from_str("a#1").unwrap();
+        assert!(resolve(&index, root.clone(), (1, 0, 0)).is_err());
+        index.lock.add("b", (1, 0, 0));
+        let solution = resolve(&index, root, (1, 0, 0)).unwrap();
+        assert_eq!(
+            solution.get(&Package::from_str("b#1").unwrap()),
+            Some(&(1, 0, 0).into())
+        );
+    }
+
+    #[test]
+    fn yanked_versions_are_avoided() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps::<core::ops::RangeFull>("b", (1, 0, 0), &[]);
+        index.add_deps::<core::ops::RangeFull>("b", (2, 0, 0), &[]);
+        index.set_status("b", (2, 0, 0), Status::Yanked);
+        let root = Package::from_str("a#1").unwrap();
+        let solution = resolve(&index, root, (1, 0, 0)).unwrap();
+        assert_eq!(
+            solution.get(&Package::from_str("b#1").unwrap()),
+            Some(&(1, 0, 0).into())
+        );
+    }
+}
@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{Index, PackageName};
+use crate::multiversion_optional_deps::Package;
+use pubgrub::error::PubGrubError;
+use pubgrub::type_aliases::{Map, SelectedDependencies};
+use pubgrub::version::SemanticVersion as SemVer;
+
+/// A solution with as few duplicated packages as could be found.
+pub struct MinimalSolution {
+    /// The selected packages.
+    pub solution: SelectedDependencies<Package, SemVer>,
+    /// Number of buckets selected in addition to the first one of each package.
+    pub duplicates: usize,
+}
+
+impl Index {
+    /// Resolve, preferring solutions with fewer duplicated packages.
+    ///
+    /// After a first resolution, each duplicated package is in turn capped
+    /// to one bucket less than selected, and the resolution is run again.
+    /// A cap is kept if a solution still exists, and dropped otherwise,
+    /// until no duplicated package can be reduced anymore.
+    /// This greedy search does not guarantee the global minimum.
+    /// The caps are tried on a copy of the index, which is left unchanged.
+    #[allow(clippy::result_large_err)]
+    pub fn resolve_with_fewest_duplicates(
+        &self,
+        package: Package,
+        version: impl Into<SemVer>,
+    ) -> Result<MinimalSolution, PubGrubError<Package, SemVer>> {
+        let version = version.into();
+        let mut capped = self.clone();
+        let mut solution = pubgrub::solver::resolve(&capped, package.clone(), version)?;
+        let mut irreducible = Vec::new();
+        loop {
+            let counts = bucket_counts(&solution);
+            let candidate = counts
+                .iter()
+                .filter(|(name, count)| **count > 1 && !irreducible.contains(*name))
+                .max_by(|(n1, c1), (n2, c2)| c1.cmp(c2).then(n2.cmp(n1)));
+            let (name, count) = match candidate {
+                None => break,
+                Some((name, count)) => (name.clone(), *count),
+            };
+            let previous_cap = capped.options.max_versions_per_package.get(&name).copied();
+            capped
+                .options
+                .max_versions_per_package
+                .insert(name.clone(), count as u32 - 1);
+            match pubgrub::solver::resolve(&capped, package.clone(), version) {
+                Ok(tighter) => solution = tighter,
+                Err(PubGrubError::NoSolution(_)) => {
+                    match previous_cap {
+                        None => capped.options.max_versions_per_package.remove(&name),
+                        Some(cap) => capped
+                            .options
+                            .max_versions_per_package
+                            .insert(name.clone(), cap),
+                    };
+                    irreducible.push(name);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        let duplicates = duplicates(&solution);
+        Ok(MinimalSolution {
+            solution,
+            duplicates,
+        })
+    }
+}
+
+/// Number of buckets selected in addition to the first one of each package.
+pub fn duplicates(solution: &SelectedDependencies<Package, SemVer>) -> usize {
+    bucket_counts(solution)
+        .values()
+        .map(|count| count - 1)
+        .sum()
+}
+
+/// Number of selected buckets of each package.
+fn bucket_counts(solution: &SelectedDependencies<Package, SemVer>) -> Map<PackageName, usize> {
+    let mut counts: Map<PackageName, usize> = Map::default();
+    for package in solution.keys() {
+        if let Package::Bucket(bucket) = package {
+            *counts.entry(bucket.name.clone()).or_default() += 1;
+        }
+    }
+    counts
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn shared_version_is_preferred() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps::<core::ops::RangeFull>("d", (1, 0, 0), &[]);
+        index.add_deps::<core::ops::RangeFull>("d", (2, 0, 0), &[]);
+        let root = Package::from_str("a#1").unwrap();
+        let minimal = index
+            .resolve_with_fewest_duplicates(root, (1, 0, 0))
+            .unwrap();
+        assert_eq!(minimal.duplicates, 0);
+        assert!(minimal
+            .solution
+            .contains_key(&Package::from_str("d#1").unwrap()));
+        assert!(!minimal
+            .solution
+            .contains_key(&Package::from_str("d#2").unwrap()));
+        assert!(index.options.max_versions_per_package.is_empty());
+    }
+
+    #[test]
+    fn unavoidable_duplicates_are_counted() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (2, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps::<core::ops::RangeFull>("d", (1, 0, 0), &[]);
+        index.add_deps::<core::ops::RangeFull>("d", (2, 0, 0), &[]);
+        let root = Package::from_str("a#1").unwrap();
+        let minimal = index
+            .resolve_with_fewest_duplicates(root, (1, 0, 0))
+            .unwrap();
+        assert_eq!(minimal.duplicates, 1);
+        assert_eq!(duplicates(&minimal.solution), 1);
+    }
+
+    #[test]
+    fn many_unavoidable_duplicates_keep_the_first_solution() {
+        let mut index = Index::new();
+        for major in 1..=10 {
+            let dependent = format!("b{}", major);
+            index.add_deps("a", (1, 0, 0), &[(dependent.as_str(), .., &[])]);
+            let d = (major, 0, 0)..(major + 1, 0, 0);
+            index.add_deps(dependent.as_str(), (1, 0, 0), &[("d", d, &[])]);
+            index.add_deps::<core::ops::RangeFull>("d", (major, 0, 0), &[]);
+        }
+        let root = Package::from_str("a#1").unwrap();
+        let first = pubgrub::solver::resolve(&index, root.clone(), (1, 0, 0)).unwrap();
+        let minimal = index
+            .resolve_with_fewest_duplicates(root, (1, 0, 0))
+            .unwrap();
+        assert_eq!(minimal.duplicates, 9);
+        assert_eq!(minimal.solution, first);
+    }
+}
@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 // https://github.com/pubgrub-rs/advanced_dependency_providers/
 
-use crate::index::{Dep, Index};
+use crate::index::{Dep, Index, PackageName, Policy};
 use core::borrow::Borrow;
 use core::fmt::Display;
 use itertools::Either;
@@ -17,28 +17,63 @@ pub enum Package {
     /// "a#1"
     Bucket(Bucket),
     /// source -> target
+    /// The target is the name under which the source depends on the target package,
+    /// which differs from the target package name for renamed dependencies.
+    /// The optional feature is the one of the source gating the dependency, if any.
     Proxy {
         source: (Bucket, SemVer),
+        optional: Option<String>,
         target: String,
         feature: Option<String>
     },
     Feature { base: Bucket, feature: String },
+    /// Marker that every bucket of a singleton package depends on.
+    /// Its versions are the bucket majors, so only one bucket can be selected.
+    Singleton(PackageName),
+    /// Marker that every bucket of a package with a given `links` value depends on.
+    /// Its versions are (index of the package among those sharing the value, bucket major, 0),
+    /// so only one bucket of one of those packages can be selected.
+    Links(String),
+    /// Marker that every bucket of a package with a cap on coexisting versions depends on,
+    /// to pick which slot that bucket occupies.
+    /// Its versions are the slot indices (i, 0, 0).
+    SlotOf(Bucket),
+    /// One of the slots of a package with a cap on coexisting versions.
+    /// Its versions are the bucket majors, so a slot holds at most one bucket,
+    /// and each slot but the first depends on the previous one holding a lower major.
+    Slot { name: PackageName, slot: u32 },
 }
 
 /// A bucket corresponds to a given package, and match versions in a range identified by their
 /// major component.
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct Bucket {
-    pub name: String,
-    pub bucket: u32, // 1 maps to the range 1.0.0 <= v < 2.0.0
+    pub name: PackageName,
+    /// Some(1) maps to the range 1.0.0 <= v < 2.0.0.
+    /// None is for packages with the single version policy, the bucket then spans all versions.
+    pub bucket: Option<u32>,
+}
+
+impl Bucket {
+    /// Range of versions in the bucket.
+    pub fn range(&self) -> Range<SemVer> {
+        match self.bucket {
+            Some(major) => Range::between((major, 0, 0), (major + 1, 0, 0)),
+            None => Range::any(),
+        }
+    }
 }
 
 impl Package {
-    fn pkg_name(&self) -> &String {
+    fn pkg_name(&self) -> Option<&PackageName> {
         match self {
-            Package::Bucket(b) => &b.name,
-            Package::Proxy { source, .. } => &source.0.name,
-            Package::Feature { base, .. } => &base.name,
+            Package::Bucket(b) => Some(&b.name),
+            Package::Proxy { source, .. } => Some(&source.0.name),
+            Package::Feature { base, .. } => Some(&base.name),
+            Package::Singleton(name) => Some(name),
+            Package::Links(_) => None,
+            Package::SlotOf(bucket) => Some(&bucket.name),
+            Package::Slot { name, .. } => Some(name),
         }
     }
 }
@@ -46,29 +81,81 @@ impl Package {
 impl FromStr for Package {
     type Err = String;
     /// "a#1" -> Package::Bucket
+    /// "a" -> Package::Bucket of a package with the single version policy
+    /// "a#1/feat" -> Package::Feature
+    /// "a(git+url)#1" -> Package::Bucket of a package from a git repository
+    /// "a#1@1.0.0->b", "a#1@1.0.0/feat->b" and "a#1/opt@1.0.0->b" -> Package::Proxy
+    /// "a!", "links=z", "a#1:slot" and "a:slot0" -> markers
     fn from_str(pkg: &str) -> Result<Self, Self::Err> {
-        let mut pkg_parts = pkg.split('#');
-        match (pkg_parts.next(), pkg_parts.next()) {
-            (Some(name), Some(version)) => {
-                let mut pkg_parts = version.split('/');
-                match (pkg_parts.next(), pkg_parts.next()) {
-                    (Some(bucket), None) =>
-                        Ok(Package::Bucket(Bucket {
-                            name: name.to_string(),
-                            bucket: bucket.parse().unwrap(),
-                        })),
-                    (Some(bucket), Some(feat)) => Ok(Package::Feature {
-                        base: Bucket {
-                            name: name.to_string(),
-                            bucket: bucket.parse().unwrap(),
-                        },
-                        feature: feat.to_string(),
-                    }),
-                    _ => Err(format!("{} is not a valid package name", pkg)),
-                }
-
-            },
-            _ => Err(format!("{} is not a valid package name", pkg)),
+        let invalid = || format!("{} is not a valid package name", pkg);
+        if let Some(links) = pkg.strip_prefix("links=") {
+            return Ok(Package::Links(links.to_string()));
+        }
+        if let Some(name) = pkg.strip_suffix('!') {
+            return Ok(Package::Singleton(name.parse()?));
+        }
+        // The source may contain '#', '/', '@' and ':', so the name ends after the source if any.
+        let name_end = match pkg.find(['(', '#', '/', '@', ':']) {
+            Some(i) if pkg[i..].starts_with('(') => {
+                pkg[i..].find(')').map(|j| i + j + 1).ok_or_else(invalid)?
+            }
+            Some(i) => i,
+            None => pkg.len(),
+        };
+        let (name, rest) = pkg.split_at(name_end);
+        let bucket = |bucket: &str| match bucket.strip_prefix('#') {
+            Some(bucket) => bucket.parse().map(Some).map_err(|_| invalid()),
+            None if bucket.is_empty() => Ok(None),
+            None => Err(invalid()),
+        };
+        if let Some((source, target)) = rest.split_once("->") {
+            let (source_bucket, version) = source.split_once('@').ok_or_else(invalid)?;
+            let (source_bucket, optional) = match source_bucket.split_once('/') {
+                Some((source_bucket, optional)) => (source_bucket, Some(optional.to_string())),
+                None => (source_bucket, None),
+            };
+            let (version, feature) = match version.split_once('/') {
+                Some((version, feature)) => (version, Some(feature.to_string())),
+                None => (version, None),
+            };
+            let source_bucket = Bucket {
+                name: name.parse()?,
+                bucket: bucket(source_bucket)?,
+            };
+            let version = version.parse().map_err(|_| invalid())?;
+            return Ok(Package::Proxy {
+                source: (source_bucket, version),
+                optional,
+                target: target.to_string(),
+                feature,
+            });
+        }
+        if let Some(slot_of) = rest.strip_suffix(":slot") {
+            return Ok(Package::SlotOf(Bucket {
+                name: name.parse()?,
+                bucket: bucket(slot_of)?,
+            }));
+        }
+        if let Some(slot) = rest.strip_prefix(":slot") {
+            return Ok(Package::Slot {
+                name: name.parse()?,
+                slot: slot.parse().map_err(|_| invalid())?,
+            });
+        }
+        let (bucket_part, feature) = match rest.split_once('/') {
+            Some((bucket, feature)) => (bucket, Some(feature)),
+            None => (rest, None),
+        };
+        let base = Bucket {
+            name: name.parse()?,
+            bucket: bucket(bucket_part)?,
+        };
+        match feature {
+            None => Ok(Package::Bucket(base)),
+            Some(feature) => Ok(Package::Feature {
+                base,
+                feature: feature.to_string(),
+            }),
         }
     }
 }
@@ -77,30 +164,42 @@ impl Display for Package {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Package::Bucket(pkg) => write!(f, "{}", pkg),
-            Package::Proxy { source, target, feature } =>
+            Package::Proxy { source, optional, target, feature } => {
+                write!(f, "{}", source.0)?;
+                if let Some(optional) = optional {
+                    write!(f, "/{}", optional)?;
+                }
                 match feature {
-                    None => write!(f, "{}@{}->{}", source.0, source.1, target),
-                    Some(feat) => write!(f, "{}@{}/{}->{}", source.0, source.1, feat, target),
-                },
+                    None => write!(f, "@{}->{}", source.1, target),
+                    Some(feat) => write!(f, "@{}/{}->{}", source.1, feat, target),
+                }
+            }
             Package::Feature { base, feature } => write!(f, "{}/{}", base, feature),
+            Package::Singleton(name) => write!(f, "{}!", name),
+            Package::Links(links) => write!(f, "links={}", links),
+            Package::SlotOf(bucket) => write!(f, "{}:slot", bucket),
+            Package::Slot { name, slot } => write!(f, "{}:slot{}", name, slot),
         }
     }
 }
 
 impl Display for Bucket {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}#{}", self.name, self.bucket)
+        match self.bucket {
+            Some(bucket) => write!(f, "{}#{}", self.name, bucket),
+            None => write!(f, "{}", self.name),
+        }
     }
 }
 
 impl Index {
-    /// List existing versions for a given package with newest versions first.
+    /// List existing versions for a given package in the version order of the options.
     pub fn list_versions(&self, package: &Package) -> impl Iterator<Item = SemVer> + '_ {
         match package {
             // If we are on a bucket, we need to filter versions
             // to only keep those within the bucket.
             Package::Bucket(p) | Package::Feature { base : p, feature : _} => {
-                let bucket_range = Range::between((p.bucket, 0, 0), (p.bucket + 1, 0, 0));
+                let bucket_range = p.range();
                 Either::Left(
                     self.available_versions(&p.name)
                         .filter(move |v| bucket_range.contains(*v))
@@ -109,31 +208,121 @@ impl Index {
             }
             // If we are on a proxy, there is one version per bucket in the target package.
             // We can additionally filter versions to only those inside the dependency range.
-            Package::Proxy { target, source, feature : _ } => {
-                let dep_range = self
-                    .packages
-                    .get(&source.0.name)
-                    .unwrap()
-                    .get(&source.1)
-                    .unwrap()
-                    .mandatory
-                    .get(target)
-                    .unwrap();
-                Either::Right(bucket_versions(
-                    self.available_versions(&target)
-                        .filter(move |v| dep_range.range.contains(v))
-                        .cloned(),
-                ))
+            // A proxy of a dependency that does not exist has no versions.
+            Package::Proxy { target, source, optional, feature : _ } => {
+                let versions = self.proxied_dep(source, optional.as_deref(), target).into_iter().flat_map(move |dep| {
+                    self.available_versions(&dep.package)
+                        .filter(move |v| dep.range.contains(v))
+                        .cloned()
+                });
+                Either::Right(Either::Left(bucket_versions(versions)))
+            }
+            marker => Either::Right(Either::Right(self.marker_versions(marker).into_iter())),
+        }
+    }
+
+    /// List versions of the markers limiting duplication.
+    fn marker_versions(&self, marker: &Package) -> Vec<SemVer> {
+        match marker {
+            // A singleton has one version per bucket of the package.
+            Package::Singleton(name) => {
+                bucket_versions(self.available_versions(name).cloned()).collect()
+            }
+            // Slots are filled in order of majors, so slot i cannot hold any of the i lowest ones.
+            Package::Slot { name, slot } => {
+                let mut majors = self.bucket_majors(name);
+                majors.drain(..majors.len().min(*slot as usize));
+                majors.into_iter().rev().map(|major| (major, 0, 0).into()).collect()
+            }
+            // Links have one version per bucket of every package sharing the `links` value.
+            Package::Links(links) => self
+                .linking_packages(links)
+                .into_iter()
+                .enumerate()
+                .flat_map(|(index, name)| {
+                    bucket_versions(self.available_versions(name).cloned()).map(move |v| {
+                        let (major, _, _) = v.into();
+                        (index as u32, major, 0).into()
+                    })
+                })
+                .collect(),
+            // A bucket can pick any of the slots of its package
+            // that does not need more lower majors than there are.
+            Package::SlotOf(bucket) => {
+                let max_versions = self.options.max_versions(&bucket.name).unwrap_or(0);
+                let lower = self
+                    .bucket_majors(&bucket.name)
+                    .iter()
+                    .filter(|major| Some(**major) < bucket.bucket)
+                    .count() as u32;
+                (0..max_versions.min(lower + 1))
+                    .map(|slot| (slot, 0, 0).into())
+                    .collect()
             }
+            _ => Vec::new(),
         }
     }
+
+    /// Majors of the available versions of a package, in increasing order.
+    fn bucket_majors(&self, package: &PackageName) -> Vec<u32> {
+        let mut majors: Vec<u32> = self
+            .available_versions(package)
+            .map(|v| {
+                let (major, _, _) = (*v).into();
+                major
+            })
+            .collect();
+        majors.sort_unstable();
+        majors.dedup();
+        majors
+    }
+
+    /// Dependencies of a bucket on the markers enforcing that
+    /// singleton and `links` packages are selected only once,
+    /// and that capped packages do not have too many buckets selected.
+    /// Packages with the single version policy only need the `links` marker.
+    fn uniqueness_deps(
+        &self,
+        bucket: &Bucket,
+        version: &SemVer,
+    ) -> impl Iterator<Item = (Package, Range<SemVer>)> {
+        let (major, _, _) = (*version).into();
+        let multiple = bucket.bucket.is_some();
+        let singleton = if multiple && self.singletons.contains(&bucket.name) {
+            Some((
+                Package::Singleton(bucket.name.clone()),
+                Range::exact((major, 0, 0)),
+            ))
+        } else {
+            None
+        };
+        let links = self.links.get(&bucket.name).map(|links| {
+            let index = self
+                .linking_packages(links)
+                .iter()
+                .position(|name| *name == &bucket.name)
+                .unwrap();
+            (
+                Package::Links(links.clone()),
+                Range::exact((index as u32, major, 0)),
+            )
+        });
+        // Packages with no more majors than their cap never need slots.
+        let slot = self
+            .options
+            .max_versions(&bucket.name)
+            .filter(|max| multiple && self.bucket_majors(&bucket.name).len() > *max as usize)
+            .map(|_| (Package::SlotOf(bucket.clone()), Range::any()));
+        singleton.into_iter().chain(links).chain(slot)
+    }
 }
 
 /// Take a list of versions, and output a list of the corresponding bucket versions.
 /// So [1.1, 1.2, 2.3] -> [1.0, 2.0]
 fn bucket_versions(versions: impl Iterator<Item = SemVer>) -> impl Iterator<Item = SemVer> {
     let mut current_bucket = None;
-    // This filter_map makes the hypothesis that versions are sorted in a normal or reverse order.
+    // This filter_map makes the hypothesis that versions are sorted in a normal or reverse order,
+    // which holds for every version order of the options.
     // Would need a bit more work if they are not ordered due to prioritizations, etc.
     versions.filter_map(move |v| {
         let v_bucket = Some(bucket_version(v));
@@ -156,9 +345,19 @@ impl DependencyProvider<Package, SemVer> for Index {
         &self,
         potential_packages: impl Iterator<Item = (T, U)>,
     ) -> Result<(T, Option<SemVer>), Box<dyn std::error::Error>> {
+        // Slots are picked once every other package is decided, bucket by bucket in order of majors,
+        // each bucket taking the lowest free slot, so that slots fill up like a counter.
+        let mut potential: Vec<(T, U)> = potential_packages.collect();
+        let slot_order = |package: &Package| match package {
+            Package::SlotOf(bucket) => (1, bucket.bucket),
+            Package::Slot { .. } => (2, None),
+            _ => (0, None),
+        };
+        let first = potential.iter().map(|(p, _)| slot_order(p.borrow())).min();
+        potential.retain(|(p, _)| Some(slot_order(p.borrow())) == first);
         Ok(pubgrub::solver::choose_package_with_fewest_versions(
             |p| self.list_versions(p),
-            potential_packages,
+            potential.into_iter(),
         ))
     }
 
@@ -167,7 +366,39 @@ impl DependencyProvider<Package, SemVer> for Index {
         package: &Package,
         version: &SemVer,
     ) -> Result<Dependencies<Package, SemVer>, Box<dyn std::error::Error>> {
-        let all_versions = match self.packages.get(package.pkg_name()) {
+        match package {
+            // Uniqueness markers only exist to be depended upon.
+            Package::Singleton(_) | Package::Links(_) | Package::Slot { slot: 0, .. } => {
+                return Ok(Dependencies::Known(Map::default()));
+            }
+            // Slots are filled in order of majors, so that buckets cannot be swapped between them:
+            // a slot can only hold a bucket if the previous slot holds a lower one.
+            Package::Slot { name, slot } => {
+                let mut deps = Map::default();
+                deps.insert(
+                    Package::Slot { name: name.clone(), slot: slot - 1 },
+                    Range::strictly_lower_than(*version),
+                );
+                return Ok(Dependencies::Known(deps));
+            }
+            // Picking slot i for a bucket puts that bucket in slot i.
+            Package::SlotOf(bucket) => {
+                let (slot, _, _) = (*version).into();
+                // Only packages with multiple versions have slots.
+                let major = match bucket.bucket {
+                    None => return Ok(Dependencies::Unknown),
+                    Some(major) => major,
+                };
+                let mut deps = Map::default();
+                deps.insert(
+                    Package::Slot { name: bucket.name.clone(), slot },
+                    Range::exact((major, 0, 0)),
+                );
+                return Ok(Dependencies::Known(deps));
+            }
+            _ => {}
+        }
+        let all_versions = match package.pkg_name().and_then(|name| self.packages.get(name)) {
             None => return Ok(Dependencies::Unknown),
             Some(all_versions) => all_versions,
         };
@@ -178,24 +409,30 @@ impl DependencyProvider<Package, SemVer> for Index {
                     None => return Ok(Dependencies::Unknown),
                     Some(deps) => deps,
                 };
-                // If we asked for a base package, we return the mandatory dependencies.
-                Ok(Dependencies::Known(from_deps(pkg, version, &deps.mandatory)))
+                // If we asked for a base package, we return the mandatory dependencies,
+                // and the uniqueness markers if the package cannot be duplicated.
+                let mut all_deps = self.dependency_constraints(pkg, version, None, &deps.mandatory);
+                all_deps.extend(self.uniqueness_deps(pkg, version));
+                Ok(Dependencies::Known(all_deps))
             },
-            Package::Proxy { source, target, feature } => {
+            Package::Proxy { source, optional, target, feature } => {
                 // If this is a proxy package, it depends on a single bucket package, the target,
                 // at a range of versions corresponding to the bucket range of the version asked,
                 // intersected with the original dependency range.
-                let proxy_deps = match all_versions.get(&source.1) {
+                let proxied_dep = match self.proxied_dep(source, optional.as_deref(), target) {
                     None => return Ok(Dependencies::Unknown),
                     Some(d) => d,
                 };
                 let (target_bucket, _, _) = version.clone().into();
-                let bucket_range = Range::between((target_bucket, 0, 0), (target_bucket + 1, 0, 0));
-                let target_range = proxy_deps.mandatory.get(target).unwrap();
                 let mut deps = Map::default();
+                let bucket_range = Range::between((target_bucket, 0, 0), (target_bucket + 1, 0, 0));
+                // Only renamed dependencies go through a proxy for single version packages.
                 let bucket = Bucket {
-                    name: target.clone(),
-                    bucket: target_bucket,
+                    name: proxied_dep.package.clone(),
+                    bucket: match self.policy(&proxied_dep.package) {
+                        Policy::Multiple => Some(target_bucket),
+                        Policy::Single => None,
+                    },
                 };
                 let dep = match feature {
                     None => Package::Bucket(bucket),
@@ -203,7 +440,7 @@ impl DependencyProvider<Package, SemVer> for Index {
                 };
                 deps.insert(
                     dep,
-                    bucket_range.intersection(&target_range.range),
+                    bucket_range.intersection(&proxied_dep.range),
                 );
                 Ok(Dependencies::Known(deps))
             }
@@ -216,7 +453,7 @@ impl DependencyProvider<Package, SemVer> for Index {
                 match deps.optional.get(feature) {
                     None => Ok(Dependencies::Unknown),
                     Some(feature_deps) => {
-                        let mut all_deps = from_deps(base, version, feature_deps);
+                        let mut all_deps = self.dependency_constraints(base, version, Some(feature), feature_deps);
                         all_deps.insert(
                             Package::Bucket(base.clone()),
                             Range::exact(version.clone()),
@@ -225,49 +462,76 @@ impl DependencyProvider<Package, SemVer> for Index {
                     }
                 }
             },
+            Package::Singleton(_) | Package::Links(_) | Package::SlotOf(_) | Package::Slot { .. } => {
+                unreachable!("markers are handled first")
+            }
         }
     }
 }
 
-/// Helper function to convert Index deps into what is expected by the dependency provider.
-fn from_deps(pkg: &Bucket, version: &SemVer, deps: &Map<String, Dep>) -> DependencyConstraints<Package, SemVer> {
-    deps.iter()
-        .flat_map(|(name, dep)| {
-            let feature_count = dep.features.len();
-            dep.features
-                .iter()
-                .map(move |feat| {
-                    if let Some(bucket) = single_bucket_spanned(&dep.range) {
-                        let name = name.clone();
-                        let bucket_dep = Bucket { name, bucket };
-                        (Package::Feature { base: bucket_dep, feature: feat.clone() }, dep.range.clone())
-                    } else {
-                        let proxy = Package::Proxy {
-                            source: (pkg.clone(), version.clone()),
-                            target: name.clone(),
-                            feature: Some(feat.to_string()),
-                        };
-                        (proxy, Range::any())
-                    }
-                })
-                .chain(std::iter::once(
-                    if let Some(bucket) = single_bucket_spanned(&dep.range) {
-                        let name = name.clone();
-                        let bucket_dep = Bucket { name, bucket };
-                        (Package::Bucket(bucket_dep), dep.range.clone())
-                    } else {
-                        let proxy = Package::Proxy {
-                            source: (pkg.clone(), version.clone()),
-                            target: name.clone(),
-                            feature: None
-                        };
-                        (proxy, Range::any())
-                    }
-                ))
-                // If there was no feature, we take the base package, otherwise, we don't.
-                .take(feature_count.max(1))
-        })
-        .collect()
+impl Index {
+    /// Helper function to convert Index deps into what is expected by the dependency provider.
+    /// Optional deps are given with the feature gating them, to find them back from their proxies.
+    fn dependency_constraints(&self, pkg: &Bucket, version: &SemVer, optional: Option<&String>, deps: &Map<String, Dep>) -> DependencyConstraints<Package, SemVer> {
+        deps.iter()
+            .flat_map(|(name, dep)| {
+                let feature_count = dep.features.len();
+                dep.features
+                    .iter()
+                    .map(move |feat| {
+                        if let Some(bucket_dep) = self.direct_bucket(name, dep) {
+                            (Package::Feature { base: bucket_dep, feature: feat.clone() }, dep.range.clone())
+                        } else {
+                            let proxy = Package::Proxy {
+                                source: (pkg.clone(), version.clone()),
+                                optional: optional.cloned(),
+                                target: name.clone(),
+                                feature: Some(feat.to_string()),
+                            };
+                            (proxy, Range::any())
+                        }
+                    })
+                    .chain(std::iter::once(
+                        if let Some(bucket_dep) = self.direct_bucket(name, dep) {
+                            (Package::Bucket(bucket_dep), dep.range.clone())
+                        } else {
+                            let proxy = Package::Proxy {
+                                source: (pkg.clone(), version.clone()),
+                                optional: optional.cloned(),
+                                target: name.clone(),
+                                feature: None
+                            };
+                            (proxy, Range::any())
+                        }
+                    ))
+                    // If there was no feature, we take the base package, otherwise, we don't.
+                    .take(feature_count.max(1))
+            })
+            .collect()
+    }
+
+    /// The bucket a dependency can directly depend upon,
+    /// or None if a proxy is needed to choose between multiple buckets.
+    /// Renamed dependencies always go through a proxy, to keep their name in the resolution.
+    fn direct_bucket(&self, name: &str, dep: &Dep) -> Option<Bucket> {
+        if name != dep.package.name {
+            return None;
+        }
+        let bucket = match self.policy(&dep.package) {
+            Policy::Single => None,
+            Policy::Multiple => Some(single_bucket_spanned(&dep.range)?),
+        };
+        Some(Bucket { name: dep.package.clone(), bucket })
+    }
+
+    /// The dependency behind a proxy, mandatory or gated by the given optional feature.
+    pub(crate) fn proxied_dep(&self, source: &(Bucket, SemVer), optional: Option<&str>, target: &str) -> Option<&Dep> {
+        let deps = self.packages.get(&source.0.name)?.get(&source.1)?;
+        match optional {
+            None => deps.mandatory.get(target),
+            Some(optional) => deps.optional.get(optional)?.get(target),
+        }
+    }
 }
 
 /// If the range is fully contained within one bucket,
@@ -288,6 +552,163 @@ fn single_bucket_spanned(range: &Range<SemVer>) -> Option<u32> {
 
 // TESTS #######################################################################
 
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::options::VersionOrder;
+    type R = core::ops::RangeFull;
+
+    #[test]
+    fn duplication_policies() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps::<R>("d", (1, 0, 0), &[]);
+        index.add_deps::<R>("d", (2, 0, 0), &[]);
+        let root = Package::from_str("a#1").unwrap();
+
+        // Multiple versions: b picks the newest d.
+        let solution = pubgrub::solver::resolve(&index, root.clone(), (1, 0, 0)).unwrap();
+        assert!(solution.contains_key(&Package::from_str("d#1").unwrap()));
+        assert!(solution.contains_key(&Package::from_str("d#2").unwrap()));
+
+        // Single version: b and c share d, without any proxy.
+        index.set_policy("d", Policy::Single);
+        let solution = pubgrub::solver::resolve(&index, root, (1, 0, 0)).unwrap();
+        assert_eq!(
+            solution.get(&Package::from_str("d").unwrap()),
+            Some(&(1, 0, 0).into())
+        );
+        assert!(solution.keys().all(|p| !matches!(p, Package::Proxy { .. })));
+
+        // The default policy applies to packages without a specific one.
+        index.policies.clear();
+        index.default_policy = Policy::Single;
+        let solution =
+            pubgrub::solver::resolve(&index, Package::from_str("a").unwrap(), (1, 0, 0)).unwrap();
+        assert_eq!(solution.len(), 4);
+    }
+
+    #[test]
+    fn renamed_dependencies() {
+        let mut index = Index::new();
+        index.add_renamed_deps(
+            "a",
+            (1, 0, 0),
+            &[
+                ("foo_v1", "foo", (1, 0, 0)..(2, 0, 0), &[]),
+                ("foo_v2", "foo", (2, 0, 0)..(3, 0, 0), &["feat"]),
+            ],
+        );
+        index.add_deps::<R>("foo", (1, 0, 0), &[]);
+        index.add_feature::<R>("foo", (2, 0, 0), "feat", &[]);
+        let root = Package::from_str("a#1").unwrap();
+        let solution = pubgrub::solver::resolve(&index, root, (1, 0, 0)).unwrap();
+        assert!(solution.contains_key(&Package::from_str("foo#1").unwrap()));
+        assert!(solution.contains_key(&Package::from_str("foo#2/feat").unwrap()));
+        let mut aliases: Vec<_> = solution
+            .keys()
+            .filter_map(|p| match p {
+                Package::Proxy { target, .. } => Some(target.as_str()),
+                _ => None,
+            })
+            .collect();
+        aliases.sort_unstable();
+        assert_eq!(aliases, vec!["foo_v1", "foo_v2"]);
+
+        // Both aliases cannot coexist if foo only allows a single version.
+        index.set_policy("foo", Policy::Single);
+        let root = Package::from_str("a#1").unwrap();
+        assert!(pubgrub::solver::resolve(&index, root, (1, 0, 0)).is_err());
+    }
+
+    #[test]
+    /// Proxies of the same dependency in two features keep the range of their own feature.
+    fn proxied_dependencies_per_feature() {
+        let mut index = Index::new();
+        index.add_feature("a", (1, 0, 0), "x", &[("b", (1, 0, 0)..(3, 0, 0), &[])]);
+        index.add_feature("a", (1, 0, 0), "y", &[("b", (3, 0, 0)..(5, 0, 0), &[])]);
+        for major in 1..5 {
+            index.add_deps::<R>("b", (major, 0, 0), &[]);
+        }
+        let root = Package::from_str("a#1/y").unwrap();
+        let solution = pubgrub::solver::resolve(&index, root, (1, 0, 0)).unwrap();
+        let proxy = Package::from_str("a#1/y@1.0.0->b").unwrap();
+        assert_eq!(solution.get(&proxy), Some(&(4, 0, 0).into()));
+        assert_eq!(
+            solution.get(&Package::from_str("b#4").unwrap()),
+            Some(&(4, 0, 0).into())
+        );
+    }
+
+    #[test]
+    /// The registry foo and its git fork are different packages,
+    /// which can coexist even if packages only allow a single version.
+    fn sources_coexist() {
+        let fork = PackageName::git("https://example.com/foo.git", "foo");
+        let mut index = Index::new();
+        index.default_policy = Policy::Single;
+        index.add_deps("a", (1, 0, 0), &[("b", .., &[])]);
+        index.add_deps("a", (1, 0, 0), &[("foo", .., &[])]);
+        index.add_renamed_deps("b", (1, 0, 0), &[("foo", fork.clone(), .., &[])]);
+        index.add_deps::<R>("foo", (1, 0, 0), &[]);
+        index.add_deps::<R>(fork, (1, 5, 0), &[]);
+        let root = Package::from_str("a").unwrap();
+        let solution = pubgrub::solver::resolve(&index, root, (1, 0, 0)).unwrap();
+        let foo = Package::from_str("foo").unwrap();
+        let fork = Package::from_str("foo(git+https://example.com/foo.git)").unwrap();
+        assert_eq!(solution.get(&foo), Some(&(1, 0, 0).into()));
+        assert_eq!(solution.get(&fork), Some(&(1, 5, 0).into()));
+    }
+
+    #[test]
+    fn encoded_packages_round_trip() {
+        for package in [
+            "a",
+            "a#1/feat",
+            "a(git+https://example.com/a.git)#2",
+            "a#1@1.0.0->b",
+            "a#1/opt@1.0.0/feat->b",
+            "a(path+../a)#0@0.3.1/feat->b",
+            "a!",
+            "links=z",
+            "a#1:slot",
+            "a:slot2",
+        ] {
+            assert_eq!(Package::from_str(package).unwrap().to_string(), package);
+        }
+        assert!(Package::from_str("a#1@1.0->b").is_err());
+    }
+
+    #[test]
+    fn version_orders() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("c", .., &[])]);
+        index.add_deps("b", (2, 0, 0), &[("c", .., &[])]);
+        index.add_deps::<R>("c", (1, 0, 0), &[]);
+        index.add_deps::<R>("c", (1, 2, 0), &[]);
+        let root = Package::from_str("a#1").unwrap();
+        let b1 = Package::from_str("b#1").unwrap();
+        let c1 = Package::from_str("c#1").unwrap();
+
+        // Oldest versions first for every package.
+        index.options.version_order = VersionOrder::Oldest;
+        let solution = pubgrub::solver::resolve(&index, root.clone(), (1, 0, 0)).unwrap();
+        assert_eq!(solution.get(&b1), Some(&(1, 0, 0).into()));
+        assert_eq!(solution.get(&c1), Some(&(1, 0, 0).into()));
+
+        // Oldest versions first only for direct dependencies of a.
+        let direct = index.direct_dependencies([&"a".into()]);
+        index.options.version_order = VersionOrder::OldestDirect(direct);
+        let solution = pubgrub::solver::resolve(&index, root, (1, 0, 0)).unwrap();
+        assert_eq!(solution.get(&b1), Some(&(1, 0, 0).into()));
+        assert_eq!(solution.get(&c1), Some(&(1, 2, 0).into()));
+    }
+}
+
 // #[cfg(test)]
 // pub mod tests {
 //     use super::*;
@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::PackageName;
+use pubgrub::type_aliases::Map;
+use std::collections::BTreeSet as Set;
+
+/// Options tuning how the index is resolved.
+#[derive(Debug, Clone, Default)]
+pub struct ResolveOptions {
+    /// Maximum number of buckets of any package that may be selected together.
+    /// None means unbounded.
+    pub max_versions: Option<u32>,
+    /// Per-package maximum number of selected buckets, overriding `max_versions`.
+    pub max_versions_per_package: Map<PackageName, u32>,
+    /// Order in which versions are tried by the resolution.
+    pub version_order: VersionOrder,
+}
+
+/// Order in which versions are tried by the resolution.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum VersionOrder {
+    /// Newest versions first.
+    #[default]
+    Newest,
+    /// Oldest versions first, to check that lower bounds of dependencies are correct.
+    Oldest,
+    /// Oldest versions first for the given packages, usually the direct dependencies
+    /// of the roots computed once by [Index::direct_dependencies](crate::index::Index::direct_dependencies),
+    /// and newest versions first for other packages.
+    OldestDirect(Set<PackageName>),
+}
+
+impl ResolveOptions {
+    /// Maximum number of buckets of a package that may be selected together.
+    pub fn max_versions(&self, package: &PackageName) -> Option<u32> {
+        self.max_versions_per_package
+            .get(package)
+            .copied()
+            .or(self.max_versions)
+    }
+}
@@ -0,0 +1,285 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{range_from_bounds, Index, PackageName};
+use crate::multiversion_optional_deps::Package;
+use core::fmt::Display;
+use core::ops::RangeBounds;
+use pubgrub::error::PubGrubError;
+use pubgrub::range::Range;
+use pubgrub::type_aliases::{Map, SelectedDependencies};
+use pubgrub::version::SemanticVersion as SemVer;
+
+/// Overrides applied on top of an index before resolution,
+/// like Cargo `[patch]`, opam pins or npm `overrides`.
+#[derive(Clone)]
+pub struct Overrides {
+    /// Packages whose versions replace those of the index.
+    /// Their singletons, `links` and policies are added to those of the index,
+    /// while the lock, the options and the default policy of the patches are ignored.
+    pub patches: Index,
+    /// Range forced on every dependency on a package.
+    pub ranges: Map<PackageName, Range<SemVer>>,
+    /// Packages pinned to a single version.
+    pub pins: Map<PackageName, SemVer>,
+}
+
+/// An override that changed the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Applied {
+    /// The versions of a package were replaced.
+    Patched(PackageName),
+    /// The range of a dependency was replaced.
+    Range {
+        dependent: PackageName,
+        version: SemVer,
+        dependency: PackageName,
+        original: Range<SemVer>,
+        forced: Range<SemVer>,
+    },
+    /// The versions of a package were restricted to a single one.
+    Pinned(PackageName, SemVer),
+    /// A package was pinned to a version that does not exist, so it was left unchanged.
+    Missing(PackageName, SemVer),
+}
+
+/// A solution, with the overrides that took effect in it.
+pub struct OverriddenSolution {
+    /// The selected packages.
+    pub solution: SelectedDependencies<Package, SemVer>,
+    /// Overrides that changed the index, for packages selected in the solution,
+    /// and the pins that could not be applied.
+    pub applied: Vec<Applied>,
+}
+
+impl Default for Overrides {
+    fn default() -> Self {
+        Self {
+            patches: Index::new(),
+            ranges: Map::default(),
+            pins: Map::default(),
+        }
+    }
+}
+
+impl Overrides {
+    /// Empty new overrides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Force the range of every dependency on a package.
+    pub fn force_range<R: RangeBounds<(u32, u32, u32)>>(
+        &mut self,
+        package: impl Into<PackageName>,
+        range: R,
+    ) {
+        self.ranges
+            .insert(package.into(), range_from_bounds(&range));
+    }
+
+    /// Pin a package to a single version.
+    pub fn pin(&mut self, package: impl Into<PackageName>, version: (u32, u32, u32)) {
+        self.pins.insert(package.into(), version.into());
+    }
+
+    /// Copy of the index with overrides applied,
+    /// and the list of overrides that changed something.
+    pub fn apply(&self, index: &Index) -> (Index, Vec<Applied>) {
+        let mut index = index.clone();
+        let mut applied = Vec::new();
+        for (package, versions) in &self.patches.packages {
+            index.packages.insert(package.clone(), versions.clone());
+            applied.push(Applied::Patched(package.clone()));
+        }
+        index
+            .singletons
+            .extend(self.patches.singletons.iter().cloned());
+        index.links.extend(self.patches.links.clone());
+        index.policies.extend(self.patches.policies.clone());
+        for (dependent, versions) in index.packages.iter_mut() {
+            for (version, deps) in versions.iter_mut() {
+                let optional = deps
+                    .optional
+                    .values_mut()
+                    .flat_map(|deps| deps.values_mut());
+                for dep in deps.mandatory.values_mut().chain(optional) {
+                    match self.ranges.get(&dep.package) {
+                        Some(forced) if forced != &dep.range => {
+                            applied.push(Applied::Range {
+                                dependent: dependent.clone(),
+                                version: *version,
+                                dependency: dep.package.clone(),
+                                original: dep.range.clone(),
+                                forced: forced.clone(),
+                            });
+                            dep.range = forced.clone();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        for (package, pinned) in &self.pins {
+            match index.packages.get_mut(package) {
+                Some(versions) if versions.contains_key(pinned) => {
+                    let count = versions.len();
+                    versions.retain(|version, _| version == pinned);
+                    if versions.len() != count {
+                        applied.push(Applied::Pinned(package.clone(), *pinned));
+                    }
+                }
+                _ => applied.push(Applied::Missing(package.clone(), *pinned)),
+            }
+        }
+        (index, applied)
+    }
+
+    /// Resolve the index with overrides applied.
+    #[allow(clippy::result_large_err)]
+    pub fn resolve(
+        &self,
+        index: &Index,
+        package: Package,
+        version: impl Into<SemVer>,
+    ) -> Result<OverriddenSolution, PubGrubError<Package, SemVer>> {
+        let (index, applied) = self.apply(index);
+        let solution = pubgrub::solver::resolve(&index, package, version)?;
+        let selected = |name: &PackageName, version: Option<&SemVer>| {
+            solution.iter().any(|(p, v)| match p {
+                Package::Bucket(bucket) => {
+                    &bucket.name == name && version.is_none_or(|version| version == v)
+                }
+                _ => false,
+            })
+        };
+        let applied = applied
+            .into_iter()
+            .filter(|applied| match applied {
+                Applied::Patched(package) => selected(package, None),
+                Applied::Range {
+                    dependent, version, ..
+                } => selected(dependent, Some(version)),
+                Applied::Pinned(package, version) => selected(package, Some(version)),
+                Applied::Missing(..) => true,
+            })
+            .collect();
+        Ok(OverriddenSolution { solution, applied })
+    }
+}
+
+impl Display for Applied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Applied::Patched(package) => write!(f, "{} is patched", package),
+            Applied::Range {
+                dependent,
+                version,
+                dependency,
+                original,
+                forced,
+            } => write!(
+                f,
+                "{} {} depends on {} {} instead of {}",
+                dependent, version, dependency, forced, original
+            ),
+            Applied::Pinned(package, version) => write!(f, "{} is pinned to {}", package, version),
+            Applied::Missing(package, version) => {
+                write!(f, "{} cannot be pinned to missing {}", package, version)
+            }
+        }
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::str::FromStr;
+    type R = core::ops::RangeFull;
+
+    fn index() -> Index {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps::<R>("c", (1, 0, 0), &[]);
+        index.add_deps::<R>("c", (1, 1, 0), &[]);
+        index.add_deps::<R>("c", (2, 0, 0), &[]);
+        index.add_deps::<R>("unused", (1, 0, 0), &[]);
+        index
+    }
+
+    fn root() -> Package {
+        Package::from_str("a#1").unwrap()
+    }
+
+    #[test]
+    fn patch_replaces_versions() {
+        let mut overrides = Overrides::new();
+        overrides
+            .patches
+            .add_deps("b", (1, 0, 0), &[("c", (2, 0, 0)..(3, 0, 0), &[])]);
+        overrides.patches.add_deps::<R>("unused", (1, 0, 1), &[]);
+        let resolved = overrides.resolve(&index(), root(), (1, 0, 0)).unwrap();
+        let c2 = Package::from_str("c#2").unwrap();
+        assert_eq!(resolved.solution.get(&c2), Some(&(2, 0, 0).into()));
+        assert_eq!(resolved.applied, vec![Applied::Patched("b".into())]);
+    }
+
+    #[test]
+    fn forced_range_applies_everywhere() {
+        let mut overrides = Overrides::new();
+        overrides.force_range("c", (2, 0, 0)..);
+        let resolved = overrides.resolve(&index(), root(), (1, 0, 0)).unwrap();
+        let c2 = Package::from_str("c#2").unwrap();
+        assert_eq!(resolved.solution.get(&c2), Some(&(2, 0, 0).into()));
+        assert_eq!(resolved.applied.len(), 1);
+        assert!(resolved.applied[0]
+            .to_string()
+            .starts_with("b 1.0.0 depends on c "));
+    }
+
+    #[test]
+    fn pin_transitive_dependency() {
+        let mut overrides = Overrides::new();
+        overrides.pin("c", (1, 0, 0));
+        overrides.pin("unused", (1, 0, 0));
+        let resolved = overrides.resolve(&index(), root(), (1, 0, 0)).unwrap();
+        let c1 = Package::from_str("c#1").unwrap();
+        assert_eq!(resolved.solution.get(&c1), Some(&(1, 0, 0).into()));
+        assert_eq!(
+            resolved.applied,
+            vec![Applied::Pinned("c".into(), (1, 0, 0).into())]
+        );
+    }
+
+    #[test]
+    fn pin_missing_version() {
+        let mut overrides = Overrides::new();
+        overrides.pin("c", (1, 5, 0));
+        let resolved = overrides.resolve(&index(), root(), (1, 0, 0)).unwrap();
+        let c1 = Package::from_str("c#1").unwrap();
+        assert_eq!(resolved.solution.get(&c1), Some(&(1, 1, 0).into()));
+        assert_eq!(
+            resolved.applied,
+            vec![Applied::Missing("c".into(), (1, 5, 0).into())]
+        );
+        assert_eq!(
+            resolved.applied[0].to_string(),
+            "c cannot be pinned to missing 1.5.0"
+        );
+    }
+
+    #[test]
+    fn patch_brings_its_singletons() {
+        let mut index = index();
+        index.add_deps("a", (1, 0, 0), &[("c", (2, 0, 0)..(3, 0, 0), &[])]);
+        let mut overrides = Overrides::new();
+        overrides.patches.add_deps::<R>("c", (1, 0, 0), &[]);
+        overrides.patches.add_deps::<R>("c", (2, 0, 0), &[]);
+        overrides.patches.add_singleton("c");
+        assert!(overrides.resolve(&index, root(), (1, 0, 0)).is_err());
+        overrides.patches.singletons.clear();
+        assert!(overrides.resolve(&index, root(), (1, 0, 0)).is_ok());
+    }
+}
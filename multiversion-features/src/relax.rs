@@ -0,0 +1,381 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{Dep, Index, PackageName};
+use crate::multiversion_optional_deps::Package;
+use crate::report::collect_externals;
+use crate::workspace::WORKSPACE_ROOT;
+use core::fmt::Display;
+use itertools::Itertools;
+use pubgrub::range::Range;
+use pubgrub::report::{DerivationTree, External};
+use pubgrub::version::SemanticVersion as SemVer;
+use std::collections::BTreeMap as Map;
+use std::collections::BTreeSet as Set;
+
+/// A change to one dependency of a package version in the index,
+/// making the constraints of a resolution weaker.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Relaxation {
+    /// Also allow a major version of the dependency, like "allow d 3.x in c 1.0.0".
+    AllowMajor {
+        dependent: PackageName,
+        version: SemVer,
+        dependency: String,
+        major: u32,
+    },
+    /// Stop requesting a feature of the dependency, like "drop feature beta on d in b 1.0.0".
+    DropFeature {
+        dependent: PackageName,
+        version: SemVer,
+        dependency: String,
+        feature: String,
+    },
+    /// Remove the dependency, like "drop dependency d in b 1.0.0".
+    DropDependency {
+        dependent: PackageName,
+        version: SemVer,
+        dependency: String,
+    },
+}
+
+impl Index {
+    /// Search for small sets of relaxations making a failed resolution succeed.
+    ///
+    /// The candidates are the dependencies involved in the derivation tree of the failure.
+    /// Sets of up to `max_size` candidates are applied to a copy of the index and resolved again,
+    /// smallest sets first, skipping the sets containing one that already succeeded.
+    /// Each returned set is thus minimal, and they are ranked by size.
+    pub fn relaxations(
+        &self,
+        package: Package,
+        version: impl Into<SemVer>,
+        derivation_tree: &DerivationTree<Package, SemVer>,
+        max_size: usize,
+    ) -> Vec<Vec<Relaxation>> {
+        let version = version.into();
+        let candidates = self.relaxation_candidates(derivation_tree);
+        let mut found: Vec<Vec<Relaxation>> = Vec::new();
+        for size in 1..=max_size.min(candidates.len()) {
+            for relaxations in candidates.iter().cloned().combinations(size) {
+                if found
+                    .iter()
+                    .any(|smaller| smaller.iter().all(|r| relaxations.contains(r)))
+                {
+                    continue;
+                }
+                let mut relaxed = self.clone();
+                for relaxation in &relaxations {
+                    relaxed.relax(relaxation);
+                }
+                if pubgrub::solver::resolve(&relaxed, package.clone(), version).is_ok() {
+                    found.push(relaxations);
+                }
+            }
+        }
+        found
+    }
+
+    /// Relaxations of the dependencies between package versions appearing in a derivation tree.
+    /// The requirements of a workspace are not relaxed, they are what is asked for.
+    fn relaxation_candidates(
+        &self,
+        derivation_tree: &DerivationTree<Package, SemVer>,
+    ) -> Set<Relaxation> {
+        let involved = involved_versions(derivation_tree);
+        let mut candidates = Set::new();
+        for (dependent, range) in &involved {
+            if dependent.name == WORKSPACE_ROOT {
+                continue;
+            }
+            let versions = self.packages.get(dependent).into_iter().flatten();
+            for (version, deps) in versions.filter(|(v, _)| range.contains(v)) {
+                let optional = deps.optional.values().flat_map(|deps| deps.iter());
+                for (name, dep) in deps.mandatory.iter().chain(optional) {
+                    if involved.contains_key(&dep.package) {
+                        self.edge_relaxations(dependent, *version, name, dep, &mut candidates);
+                    }
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Relaxations of a single dependency.
+    fn edge_relaxations(
+        &self,
+        dependent: &PackageName,
+        version: SemVer,
+        name: &str,
+        dep: &Dep,
+        candidates: &mut Set<Relaxation>,
+    ) {
+        let excluded_majors: Set<u32> = self
+            .available_versions(&dep.package)
+            .filter(|v| !dep.range.contains(v))
+            .map(|v| {
+                let (major, _, _) = (*v).into();
+                major
+            })
+            .collect();
+        for major in excluded_majors {
+            candidates.insert(Relaxation::AllowMajor {
+                dependent: dependent.clone(),
+                version,
+                dependency: name.to_string(),
+                major,
+            });
+        }
+        for feature in &dep.features {
+            candidates.insert(Relaxation::DropFeature {
+                dependent: dependent.clone(),
+                version,
+                dependency: name.to_string(),
+                feature: feature.clone(),
+            });
+        }
+        candidates.insert(Relaxation::DropDependency {
+            dependent: dependent.clone(),
+            version,
+            dependency: name.to_string(),
+        });
+    }
+
+    /// Apply a relaxation to every occurrence of the dependency,
+    /// mandatory or optional.
+    pub fn relax(&mut self, relaxation: &Relaxation) {
+        let (dependent, version, dependency) = match relaxation {
+            Relaxation::AllowMajor {
+                dependent,
+                version,
+                dependency,
+                ..
+            }
+            | Relaxation::DropFeature {
+                dependent,
+                version,
+                dependency,
+                ..
+            }
+            | Relaxation::DropDependency {
+                dependent,
+                version,
+                dependency,
+            } => (dependent, version, dependency),
+        };
+        let deps = match self
+            .packages
+            .get_mut(dependent)
+            .and_then(|versions| versions.get_mut(version))
+        {
+            None => return,
+            Some(deps) => deps,
+        };
+        let optional = deps.optional.values_mut();
+        for dep_map in std::iter::once(&mut deps.mandatory).chain(optional) {
+            match relaxation {
+                Relaxation::AllowMajor { major, .. } => {
+                    if let Some(dep) = dep_map.get_mut(dependency) {
+                        let bucket = Range::between((*major, 0, 0), (major + 1, 0, 0));
+                        dep.range = dep.range.union(&bucket);
+                    }
+                }
+                Relaxation::DropFeature { feature, .. } => {
+                    if let Some(dep) = dep_map.get_mut(dependency) {
+                        dep.features.remove(feature);
+                    }
+                }
+                Relaxation::DropDependency { .. } => {
+                    dep_map.remove(dependency);
+                }
+            }
+        }
+    }
+}
+
+/// Versions of each package mentioned by the external incompatibilities of a derivation tree.
+fn involved_versions(
+    derivation_tree: &DerivationTree<Package, SemVer>,
+) -> Map<PackageName, Range<SemVer>> {
+    let mut externals = Vec::new();
+    collect_externals(derivation_tree, &mut externals);
+    let mut involved: Map<PackageName, Range<SemVer>> = Map::new();
+    let mut involve = |package: &Package, range: &Range<SemVer>| {
+        let (name, range) = match package {
+            Package::Bucket(bucket) | Package::Feature { base: bucket, .. } => {
+                (&bucket.name, range.intersection(&bucket.range()))
+            }
+            Package::Proxy { source, .. } => (&source.0.name, Range::exact(source.1)),
+            _ => return,
+        };
+        let entry = involved.entry(name.clone()).or_insert_with(Range::none);
+        *entry = entry.union(&range);
+    };
+    for external in externals {
+        match external {
+            External::NotRoot(package, version) => involve(package, &Range::exact(*version)),
+            External::NoVersions(package, range)
+            | External::UnavailableDependencies(package, range) => involve(package, range),
+            External::FromDependencyOf(package, range, dep, dep_range) => {
+                involve(package, range);
+                involve(dep, dep_range);
+            }
+        }
+    }
+    involved
+}
+
+impl Display for Relaxation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Relaxation::AllowMajor {
+                dependent,
+                version,
+                dependency,
+                major,
+            } => write!(
+                f,
+                "allow {} {}.x in {} {}",
+                dependency, major, dependent, version
+            ),
+            Relaxation::DropFeature {
+                dependent,
+                version,
+                dependency,
+                feature,
+            } => write!(
+                f,
+                "drop feature {} on {} in {} {}",
+                feature, dependency, dependent, version
+            ),
+            Relaxation::DropDependency {
+                dependent,
+                version,
+                dependency,
+            } => write!(
+                f,
+                "drop dependency {} in {} {}",
+                dependency, dependent, version
+            ),
+        }
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use pubgrub::error::PubGrubError;
+    use std::str::FromStr;
+    type R = core::ops::RangeFull;
+
+    fn suggestions(index: &Index, max_size: usize) -> Vec<Vec<String>> {
+        let root = Package::from_str("a#1").unwrap();
+        match pubgrub::solver::resolve(index, root.clone(), (1, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => index
+                .relaxations(root, (1, 0, 0), &tree, max_size)
+                .iter()
+                .map(|set| set.iter().map(|r| r.to_string()).collect())
+                .collect(),
+            _ => panic!("expected no solution"),
+        }
+    }
+
+    #[test]
+    fn allow_another_major() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (3, 0, 0)..(4, 0, 0), &[])]);
+        index.add_deps::<R>("d", (1, 0, 0), &[]);
+        index.add_deps::<R>("d", (3, 0, 0), &[]);
+        index.add_singleton("d");
+        let suggestions = suggestions(&index, 2);
+        assert!(suggestions.iter().all(|set| set.len() == 1));
+        let allow_3 = vec!["allow d 3.x in b 1.0.0".to_string()];
+        let allow_1 = vec!["allow d 1.x in c 1.0.0".to_string()];
+        assert!(suggestions.contains(&allow_3));
+        assert!(suggestions.contains(&allow_1));
+        assert!(suggestions.contains(&vec!["drop dependency c in a 1.0.0".to_string()]));
+    }
+
+    #[test]
+    fn drop_missing_feature() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (2, 0, 0)..(3, 0, 0), &["beta"])]);
+        index.add_feature::<R>("d", (1, 0, 0), "beta", &[]);
+        index.add_deps::<R>("d", (2, 0, 0), &[]);
+        let suggestions = suggestions(&index, 1);
+        assert_eq!(suggestions[0], vec!["allow d 1.x in b 1.0.0".to_string()]);
+        assert!(suggestions.contains(&vec!["drop feature beta on d in b 1.0.0".to_string()]));
+    }
+
+    #[test]
+    fn requirements_are_not_relaxed() {
+        let mut index = Index::new();
+        index.add_deps("b", (1, 0, 0), &[("d", (2, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps::<R>("d", (1, 0, 0), &[]);
+        index.add_deps::<R>("d", (2, 0, 0), &[]);
+        index.add_singleton("d");
+        let (index, root) = index.with_workspace_root([
+            Dep {
+                package: "b".into(),
+                range: Range::any(),
+                features: Default::default(),
+            },
+            Dep {
+                package: "d".into(),
+                range: Range::between((1, 0, 0), (2, 0, 0)),
+                features: Default::default(),
+            },
+        ]);
+        let tree = match pubgrub::solver::resolve(&index, root.clone(), (0, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => tree,
+            _ => panic!("expected no solution"),
+        };
+        let suggestions: Vec<Vec<String>> = index
+            .relaxations(root, (0, 0, 0), &tree, 2)
+            .iter()
+            .map(|set| set.iter().map(|r| r.to_string()).collect())
+            .collect();
+        assert!(suggestions.contains(&vec!["allow d 1.x in b 1.0.0".to_string()]));
+        assert!(suggestions
+            .iter()
+            .flatten()
+            .all(|r| !r.contains(WORKSPACE_ROOT)));
+    }
+
+    #[test]
+    fn only_minimal_sets_are_suggested() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (2, 0, 0)..(3, 0, 0), &["x", "y"])]);
+        index.add_deps::<R>("b", (2, 0, 0), &[]);
+        let relaxed = |relaxations: &[Relaxation]| {
+            let mut relaxed = index.clone();
+            relaxations.iter().for_each(|r| relaxed.relax(r));
+            relaxed
+        };
+        let drop = |feature: &str| Relaxation::DropFeature {
+            dependent: "a".into(),
+            version: (1, 0, 0).into(),
+            dependency: "b".into(),
+            feature: feature.into(),
+        };
+        let root = Package::from_str("a#1").unwrap();
+        assert!(pubgrub::solver::resolve(&relaxed(&[drop("x")]), root.clone(), (1, 0, 0)).is_err());
+        assert!(
+            pubgrub::solver::resolve(&relaxed(&[drop("x"), drop("y")]), root, (1, 0, 0)).is_ok()
+        );
+        let suggestions = suggestions(&index, 2);
+        assert!(suggestions.contains(&vec![
+            "drop feature x on b in a 1.0.0".to_string(),
+            "drop feature y on b in a 1.0.0".to_string(),
+        ]));
+        assert!(suggestions.contains(&vec!["drop dependency b in a 1.0.0".to_string()]));
+        assert!(!suggestions
+            .iter()
+            .any(|set| set.len() == 2 && set.iter().any(|r| r.starts_with("drop dependency"))));
+    }
+}
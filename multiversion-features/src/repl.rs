@@ -0,0 +1,254 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::import::import_registry;
+use crate::index::{Dep, Deps, Index, PackageName};
+use crate::index_file::parse_dep;
+use crate::multiversion_optional_deps::Package;
+use crate::resolution::Resolution;
+use crate::workspace::remove_workspace_root;
+use pubgrub::error::PubGrubError;
+use pubgrub::solver::{Dependencies, DependencyProvider};
+use pubgrub::version::SemanticVersion as SemVer;
+use std::fmt::Write;
+use std::str::FromStr;
+
+pub const HELP: &str = "\
+Commands:
+    load FILE                         Replace the index by an index file
+    import FILE                       Replace the index by a Cargo registry index file
+    add NAME VERSION [DEP...]         Add a version, or dependencies to it
+    feature NAME VERSION FEAT [DEP...]
+                                      Add a feature, with its dependencies
+    remove NAME [VERSION [DEP]]       Remove a package, a version, or a dependency of a version
+    versions PACKAGE                  List the versions of an encoded package, like d#1,
+                                      d#1/alpha or a#1@1.0.0->d, in the order they are tried
+    deps PACKAGE VERSION              Dependencies of an encoded package version
+    resolve [DEP...]                  Resolve dependencies together, or the last ones again
+    help                              Print this help
+    quit                              Exit
+
+Dependencies are written NAME[@REQ][:FEATURE,...], like d@^1.2:alpha,beta.
+";
+
+/// An interactive session editing an index and resolving it.
+pub struct Repl {
+    pub index: Index,
+    /// Dependencies of the last resolution, resolved again by a bare `resolve`.
+    last_resolved: Vec<Dep>,
+}
+
+impl Repl {
+    /// New session on an index.
+    pub fn new(index: Index) -> Self {
+        Self {
+            index,
+            last_resolved: Vec::new(),
+        }
+    }
+
+    /// Execute a command line, returning what should be printed.
+    /// A failed resolution is not an error, its report is printed.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            [] => Ok(String::new()),
+            ["help"] => Ok(HELP.to_string()),
+            ["load", file] => {
+                let json = serde_json::from_str(&read(file)?).map_err(|e| e.to_string())?;
+                self.index = Index::from_json(&json)?;
+                Ok(format!("Loaded {} packages\n", self.index.packages.len()))
+            }
+            ["import", file] => {
+                self.index = Index::from_json(&import_registry(&read(file)?)?)?;
+                Ok(format!("Imported {} packages\n", self.index.packages.len()))
+            }
+            ["add", name, version, ref deps @ ..] => {
+                let deps = deps
+                    .iter()
+                    .map(|d| parse_dep(d))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let registered = self.version_entry(name, version)?;
+                for dep in deps {
+                    registered.mandatory.insert(dep.package.to_string(), dep);
+                }
+                Ok(String::new())
+            }
+            ["feature", name, version, feature, ref deps @ ..] => {
+                let deps = deps
+                    .iter()
+                    .map(|d| parse_dep(d))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let registered = self.version_entry(name, version)?;
+                let optional = registered.optional.entry(feature.to_string()).or_default();
+                for dep in deps {
+                    optional.insert(dep.package.to_string(), dep);
+                }
+                Ok(String::new())
+            }
+            ["remove", name] => {
+                let name = PackageName::from_str(name)?;
+                self.index
+                    .packages
+                    .remove(&name)
+                    .ok_or_else(|| format!("no package {}", name))?;
+                Ok(String::new())
+            }
+            ["remove", name, version] => {
+                let versions = self
+                    .index
+                    .packages
+                    .get_mut(&PackageName::from_str(name)?)
+                    .ok_or_else(|| format!("no package {}", name))?;
+                versions
+                    .remove(&parse_version(version)?)
+                    .ok_or_else(|| format!("no version {} of {}", version, name))?;
+                Ok(String::new())
+            }
+            ["remove", name, version, dep] => {
+                let parsed = parse_version(version)?;
+                let deps = self
+                    .index
+                    .packages
+                    .get_mut(&PackageName::from_str(name)?)
+                    .and_then(|versions| versions.get_mut(&parsed))
+                    .ok_or_else(|| format!("no version {} of {}", version, name))?;
+                let mandatory = deps.mandatory.remove(dep).is_some();
+                let mut optional = false;
+                for feature_deps in deps.optional.values_mut() {
+                    optional |= feature_deps.remove(dep).is_some();
+                }
+                if !mandatory && !optional {
+                    return Err(format!("{} {} does not depend on {}", name, version, dep));
+                }
+                Ok(String::new())
+            }
+            ["versions", package] => {
+                let package = Package::from_str(package)?;
+                let mut versions = String::new();
+                for version in self.index.list_versions(&package) {
+                    let _ = writeln!(versions, "{}", version);
+                }
+                Ok(versions)
+            }
+            ["deps", package, version] => {
+                let package = Package::from_str(package)?;
+                let version = parse_version(version)?;
+                match self.index.get_dependencies(&package, &version) {
+                    Ok(Dependencies::Known(deps)) => {
+                        let mut deps: Vec<String> = deps
+                            .iter()
+                            .map(|(dep, range)| format!("{} {}\n", dep, range))
+                            .collect();
+                        deps.sort();
+                        Ok(deps.concat())
+                    }
+                    Ok(Dependencies::Unknown) => Ok("Unknown dependencies\n".to_string()),
+                    Err(err) => Err(err.to_string()),
+                }
+            }
+            ["resolve", ref deps @ ..] => {
+                if !deps.is_empty() {
+                    self.last_resolved = deps
+                        .iter()
+                        .map(|d| parse_dep(d))
+                        .collect::<Result<_, _>>()?;
+                } else if self.last_resolved.is_empty() {
+                    return Err("nothing to resolve yet".to_string());
+                }
+                Ok(self.resolve())
+            }
+            _ => Err(format!("cannot understand {:?}, see help", line.trim())),
+        }
+    }
+
+    /// The solution of the last resolved dependencies, or the failure report.
+    fn resolve(&self) -> String {
+        let (index, root) = self
+            .index
+            .with_workspace_root(self.last_resolved.iter().cloned());
+        match pubgrub::solver::resolve(&index, root, SemVer::zero()) {
+            Ok(mut solution) => {
+                remove_workspace_root(&mut solution);
+                Resolution::from_solution(&solution).to_string()
+            }
+            Err(PubGrubError::NoSolution(tree)) => format!("{}\n", index.report(&tree)),
+            Err(err) => format!("{:?}\n", err),
+        }
+    }
+
+    /// Dependencies of a package version, registered if new.
+    fn version_entry(&mut self, name: &str, version: &str) -> Result<&mut Deps, String> {
+        Ok(self
+            .index
+            .packages
+            .entry(PackageName::from_str(name)?)
+            .or_default()
+            .entry(parse_version(version)?)
+            .or_default())
+    }
+}
+
+fn parse_version(version: &str) -> Result<SemVer, String> {
+    SemVer::from_str(version).map_err(|e| e.to_string())
+}
+
+fn read(file: &str) -> Result<String, String> {
+    std::fs::read_to_string(file).map_err(|e| format!("cannot read {}: {}", file, e))
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_inspect_and_resolve() {
+        let mut repl = Repl::new(Index::new());
+        for line in [
+            "add a 1.0.0 b@^1 c@^1",
+            "add b 1.0.0 d@^2:beta",
+            "add c 1.0.0 d@^1:alpha",
+            "feature d 1.0.0 alpha",
+            "feature d 2.0.0 beta e",
+            "add e 1.0.0",
+        ] {
+            assert_eq!(repl.execute(line), Ok(String::new()), "{}", line);
+        }
+        assert_eq!(
+            repl.execute("resolve a").unwrap(),
+            "a v1.0.0\nb v1.0.0\nc v1.0.0\nd v1.0.0 [alpha]\nd v2.0.0 [beta]\ne v1.0.0\n"
+        );
+        assert_eq!(
+            repl.execute("versions d#x"),
+            Err("d#x is not a valid package name".to_string())
+        );
+        assert_eq!(
+            repl.execute("deps b#1@1.0.0->d 2.0.0").unwrap(),
+            "d#2 2.0.0 <= v < 3.0.0\n"
+        );
+        assert_eq!(repl.execute("versions d#2/beta").unwrap(), "2.0.0\n");
+        assert_eq!(repl.execute("versions a#1@1.0.0->zz").unwrap(), "");
+        assert_eq!(
+            repl.execute("deps a#1@1.0.0->zz 1.0.0").unwrap(),
+            "Unknown dependencies\n"
+        );
+        assert_eq!(
+            repl.execute("deps a:slot 0.0.0").unwrap(),
+            "Unknown dependencies\n"
+        );
+        repl.execute("remove d 2.0.0").unwrap();
+        assert_eq!(repl.execute("versions b#1@1.0.0->d").unwrap(), "");
+        assert!(repl
+            .execute("resolve")
+            .unwrap()
+            .contains("version solving failed"));
+        let malformed = repl.execute("remove b 1.0 d").unwrap_err();
+        assert_eq!(malformed, parse_version("1.0").unwrap_err());
+        repl.execute("remove b 1.0.0 d").unwrap();
+        assert!(repl
+            .execute("resolve")
+            .unwrap()
+            .starts_with("a v1.0.0\nb v1.0.0\nc v1.0.0\n"));
+    }
+}
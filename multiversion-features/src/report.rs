@@ -0,0 +1,325 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::Index;
+use crate::multiversion_optional_deps::{Bucket, Package};
+use crate::reporter::UserReporter;
+use pubgrub::range::Range;
+use pubgrub::report::{DerivationTree, External};
+use pubgrub::version::SemanticVersion as SemVer;
+
+impl Index {
+    /// Explain why no solution was found, in terms of the packages of the index.
+    /// The derivation tree is expected as returned by the solver, not yet collapsed,
+    /// so that packages without versions can be checked for yanked versions.
+    pub fn report(&self, derivation_tree: &DerivationTree<Package, SemVer>) -> String {
+        let mut externals = Vec::new();
+        collect_externals(derivation_tree, &mut externals);
+        let mut collapsed = derivation_tree.clone();
+        collapsed.collapse_no_versions();
+        let mut report = UserReporter::report_for_index(self, &collapsed);
+        let mut notes = Vec::new();
+        for external in &externals {
+            if let External::NoVersions(package, range) = external {
+                let note = self.yanked_note(package, range);
+                for note in note.into_iter().chain(self.locked_note(package, range)) {
+                    if !notes.contains(&note) {
+                        notes.push(note);
+                    }
+                }
+            }
+        }
+        notes.extend(self.uniqueness_notes(&externals));
+        for note in notes {
+            report.push('\n');
+            report.push_str(&note);
+        }
+        report
+    }
+
+    /// Note for a package where only yanked versions match the range.
+    fn yanked_note(&self, package: &Package, range: &Range<SemVer>) -> Option<String> {
+        let base = match package {
+            Package::Bucket(base) | Package::Feature { base, .. } => base,
+            _ => return None,
+        };
+        let range = range.intersection(&base.range());
+        let yanked: Vec<String> = self
+            .yanked_versions(&base.name)
+            .filter(|v| range.contains(v) && !self.lock.contains(&base.name, v))
+            .map(|v| v.to_string())
+            .collect();
+        if yanked.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "Note: only yanked versions of {} match {}: {}",
+            base.name,
+            range,
+            yanked.join(", ")
+        ))
+    }
+
+    /// Note for a locked package where only versions other than the locked ones match the range,
+    /// which happens when locked packages are pinned, like during an update.
+    fn locked_note(&self, package: &Package, range: &Range<SemVer>) -> Option<String> {
+        let base = match package {
+            Package::Bucket(base) | Package::Feature { base, .. } => base,
+            _ => return None,
+        };
+        let locked = self.lock.packages.get(&base.name)?;
+        let range = range.intersection(&base.range());
+        let mut matching = self
+            .available_versions(&base.name)
+            .filter(|v| range.contains(v));
+        if locked.iter().any(|v| range.contains(v)) || matching.next().is_none() {
+            return None;
+        }
+        let locked: Vec<String> = locked.iter().map(|v| v.to_string()).collect();
+        Some(format!(
+            "Note: {} is locked to {}, which must move to match {}",
+            base.name,
+            locked.join(", "),
+            range
+        ))
+    }
+
+    /// Notes for singleton, `links` and capped packages selected in too many buckets,
+    /// naming the dependents pulling in each bucket.
+    fn uniqueness_notes(&self, externals: &[&External<Package, SemVer>]) -> Vec<String> {
+        let mut notes = Vec::new();
+        // Each limit is identified by its explanation, with the number of buckets allowed.
+        let limits = externals.iter().filter_map(|external| match external {
+            External::FromDependencyOf(Package::Bucket(bucket), _, marker, _) => {
+                let limit = match marker {
+                    Package::Singleton(name) => (format!("{} can only be selected once", name), 1),
+                    Package::Links(links) => (
+                        format!("packages linking {} can only be selected once", links),
+                        1,
+                    ),
+                    Package::SlotOf(_) => {
+                        let max_versions = self.options.max_versions(&bucket.name)?;
+                        (
+                            format!(
+                                "at most {} versions of {} can be selected",
+                                max_versions, bucket.name
+                            ),
+                            max_versions as usize,
+                        )
+                    }
+                    _ => return None,
+                };
+                Some((limit, bucket))
+            }
+            _ => None,
+        });
+        let mut conflicts: Vec<((String, usize), Vec<&Bucket>)> = Vec::new();
+        for (limit, bucket) in limits {
+            match conflicts.iter_mut().find(|(l, _)| l == &limit) {
+                Some((_, buckets)) if !buckets.contains(&bucket) => buckets.push(bucket),
+                Some(_) => {}
+                None => conflicts.push((limit, vec![bucket])),
+            }
+        }
+        for ((explanation, allowed), buckets) in conflicts {
+            let mut pulls = Vec::new();
+            for bucket in &buckets {
+                for dependent in dependents(externals, bucket) {
+                    let pull = format!("{} depends on {}", dependent, bucket);
+                    if !pulls.contains(&pull) {
+                        pulls.push(pull);
+                    }
+                }
+            }
+            if buckets.len() <= allowed || pulls.is_empty() {
+                continue;
+            }
+            notes.push(format!(
+                "Note: {}, but {}",
+                explanation,
+                pulls.join(" and ")
+            ));
+        }
+        notes
+    }
+}
+
+/// Buckets depending on the given bucket, seeing through proxies.
+fn dependents<'e>(externals: &[&'e External<Package, SemVer>], bucket: &Bucket) -> Vec<&'e Bucket> {
+    externals
+        .iter()
+        .filter_map(|external| match external {
+            External::FromDependencyOf(dependent, _, Package::Bucket(base), _)
+            | External::FromDependencyOf(dependent, _, Package::Feature { base, .. }, _)
+                if base == bucket =>
+            {
+                match dependent {
+                    Package::Bucket(b) => Some(b),
+                    Package::Feature { base: b, .. } if b != bucket => Some(b),
+                    Package::Proxy { source, .. } => Some(&source.0),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Collect all the external incompatibilities of a derivation tree.
+pub(crate) fn collect_externals<'t>(
+    tree: &'t DerivationTree<Package, SemVer>,
+    found: &mut Vec<&'t External<Package, SemVer>>,
+) {
+    match tree {
+        DerivationTree::External(external) => found.push(external),
+        DerivationTree::Derived(derived) => {
+            collect_externals(&derived.cause1, found);
+            collect_externals(&derived.cause2, found);
+        }
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::index::Status;
+    use pubgrub::error::PubGrubError;
+    use std::str::FromStr;
+
+    #[test]
+    fn report_mentions_yanked_versions() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps::<core::ops::RangeFull>("b", (1, 0, 0), &[]);
+        index.add_deps::<core::ops::RangeFull>("b", (1, 1, 0), &[]);
+        index.add_deps::<core::ops::RangeFull>("b", (2, 0, 0), &[]);
+        index.set_status("b", (1, 0, 0), Status::Yanked);
+        index.set_status("b", (1, 1, 0), Status::Retracted);
+        let root = Package::from_str("a#1").unwrap();
+        match pubgrub::solver::resolve(&index, root, (1, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => {
+                let report = index.report(&tree);
+                assert!(report.contains("only yanked versions of b match"));
+                assert!(report.contains("1.1.0, 1.0.0"));
+            }
+            _ => panic!("expected no solution"),
+        }
+    }
+
+    #[test]
+    fn report_without_yanked_versions() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps::<core::ops::RangeFull>("b", (2, 0, 0), &[]);
+        let root = Package::from_str("a#1").unwrap();
+        match pubgrub::solver::resolve(&index, root, (1, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => {
+                assert!(!index.report(&tree).contains("yanked"));
+            }
+            _ => panic!("expected no solution"),
+        }
+    }
+
+    #[test]
+    fn singletons_are_not_duplicated() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (2, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps::<core::ops::RangeFull>("d", (1, 0, 0), &[]);
+        index.add_deps::<core::ops::RangeFull>("d", (2, 0, 0), &[]);
+        let root = Package::from_str("a#1").unwrap();
+        assert!(pubgrub::solver::resolve(&index, root.clone(), (1, 0, 0)).is_ok());
+        index.add_singleton("d");
+        match pubgrub::solver::resolve(&index, root, (1, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => {
+                let report = index.report(&tree);
+                assert!(report.contains("d can only be selected once"));
+                assert!(report.contains("b#1 depends on d#1"));
+                assert!(report.contains("c#1 depends on d#2"));
+            }
+            _ => panic!("expected no solution"),
+        }
+    }
+
+    #[test]
+    fn packages_sharing_links_conflict() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps::<core::ops::RangeFull>("b", (2, 0, 0), &[]);
+        index.add_deps::<core::ops::RangeFull>("c", (1, 0, 0), &[]);
+        index.set_links("b", "z");
+        index.set_links("c", "z");
+        let root = Package::from_str("a#1").unwrap();
+        match pubgrub::solver::resolve(&index, root, (1, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => {
+                let report = index.report(&tree);
+                assert!(report.contains("packages linking z can only be selected once"));
+                assert!(report.contains("a#1 depends on b#2"));
+                assert!(report.contains("a#1 depends on c#1"));
+            }
+            _ => panic!("expected no solution"),
+        }
+        index.links.remove(&"c".into());
+        assert!(
+            pubgrub::solver::resolve(&index, Package::from_str("a#1").unwrap(), (1, 0, 0)).is_ok()
+        );
+    }
+
+    #[test]
+    fn coexisting_versions_are_capped() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("d", (3, 0, 0)..(4, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (2, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps::<core::ops::RangeFull>("d", (1, 0, 0), &[]);
+        index.add_deps::<core::ops::RangeFull>("d", (2, 0, 0), &[]);
+        index.add_deps::<core::ops::RangeFull>("d", (3, 0, 0), &[]);
+        let root = Package::from_str("a#1").unwrap();
+        index.options.max_versions = Some(3);
+        assert!(pubgrub::solver::resolve(&index, root.clone(), (1, 0, 0)).is_ok());
+        index.options.max_versions_per_package.insert("d".into(), 2);
+        match pubgrub::solver::resolve(&index, root, (1, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => {
+                let report = index.report(&tree);
+                assert!(report.contains("at most 2 versions of d can be selected"));
+                assert!(report.contains("b#1 depends on d#1"));
+                assert!(report.contains("c#1 depends on d#2"));
+                assert!(report.contains("a#1 depends on d#3"));
+            }
+            _ => panic!("expected no solution"),
+        }
+    }
+
+    #[test]
+    /// Ten buckets of d for nine slots, or two: the resolution and its report still finish quickly.
+    fn far_beyond_the_cap() {
+        let mut index = Index::new();
+        for major in 1..=10 {
+            let dependent = format!("b{}", major);
+            index.add_deps("a", (1, 0, 0), &[(dependent.as_str(), .., &[])]);
+            let d = (major, 0, 0)..(major + 1, 0, 0);
+            index.add_deps(dependent.as_str(), (1, 0, 0), &[("d", d, &[])]);
+            index.add_deps::<core::ops::RangeFull>("d", (major, 0, 0), &[]);
+        }
+        let root = Package::from_str("a#1").unwrap();
+        index.options.max_versions = Some(10);
+        assert!(pubgrub::solver::resolve(&index, root.clone(), (1, 0, 0)).is_ok());
+        for cap in [9, 2] {
+            index.options.max_versions = Some(cap);
+            match pubgrub::solver::resolve(&index, root.clone(), (1, 0, 0)) {
+                Err(PubGrubError::NoSolution(tree)) => {
+                    let report = index.report(&tree);
+                    let limit = format!("at most {} versions of d can be selected", cap);
+                    assert!(report.contains(&limit));
+                }
+                _ => panic!("expected no solution"),
+            }
+        }
+    }
+}
@@ -0,0 +1,660 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{Index, PackageName};
+use crate::multiversion_optional_deps::{Bucket, Package};
+use crate::workspace::{is_workspace_root, requirement_package};
+use pubgrub::range::Range;
+use pubgrub::report::{DerivationTree, Derived, External, Reporter};
+use pubgrub::term::Term;
+use pubgrub::type_aliases::Map;
+use pubgrub::version::SemanticVersion as SemVer;
+
+/// Reporter explaining a failure in terms of the packages of the index,
+/// instead of the buckets, proxies and feature packages used to encode them.
+/// A dependency going through a proxy is explained as a dependency of the source package
+/// on the target package, like "a 1.0.0 depends on d 1.0.0 <= v < 3.0.0 with feature alpha",
+/// and a renamed dependency also gives its local name, like "foo 1.0.0 (as foo_v1)".
+/// The synthetic root of a workspace stands for the root requirements,
+/// so its dependencies are "the requirements ask for d" and its rejection is the failure.
+/// Lines are built like the pubgrub `DefaultStringReporter` does.
+pub struct UserReporter {
+    /// Number of explanations that have been referenced so far.
+    ref_count: usize,
+    /// Shared nodes of the derivation tree that have already been explained,
+    /// with the reference of their explanation.
+    shared_with_ref: Map<usize, usize>,
+    /// Explanation lines.
+    lines: Vec<String>,
+    /// Translation of the terms of the derivation tree.
+    user: UserTerms,
+}
+
+/// Translation of the packages of a derivation tree in terms of the packages of the index.
+pub(crate) struct UserTerms {
+    /// Range of the target behind each proxy, gathered from the dependencies of the proxy.
+    proxy_ranges: Map<Package, Range<SemVer>>,
+    /// Package behind each proxy, which may be depended upon under another name.
+    proxy_packages: Map<Package, PackageName>,
+}
+
+impl Reporter<Package, SemVer> for UserReporter {
+    type Output = String;
+
+    fn report(derivation_tree: &DerivationTree<Package, SemVer>) -> Self::Output {
+        Self::report_with(UserTerms::new(derivation_tree), derivation_tree)
+    }
+}
+
+impl UserReporter {
+    /// Report with the packages behind proxies looked up in the index,
+    /// even those whose dependencies are not part of the derivation tree.
+    pub(crate) fn report_for_index(
+        index: &Index,
+        derivation_tree: &DerivationTree<Package, SemVer>,
+    ) -> String {
+        Self::report_with(
+            UserTerms::for_index(index, derivation_tree),
+            derivation_tree,
+        )
+    }
+
+    fn report_with(user: UserTerms, derivation_tree: &DerivationTree<Package, SemVer>) -> String {
+        let mut reporter = Self {
+            ref_count: 0,
+            shared_with_ref: Map::default(),
+            lines: Vec::new(),
+            user,
+        };
+        match collapse_proxies(derivation_tree.clone()) {
+            DerivationTree::External(external) => reporter.user.external(&external),
+            DerivationTree::Derived(derived) => {
+                reporter.build_recursive(&derived);
+                reporter.lines.join("\n")
+            }
+        }
+    }
+
+    fn build_recursive(&mut self, derived: &Derived<Package, SemVer>) {
+        self.build_recursive_helper(derived);
+        if let Some(id) = derived.shared_id {
+            if !self.shared_with_ref.contains_key(&id) {
+                self.add_line_ref();
+                self.shared_with_ref.insert(id, self.ref_count);
+            }
+        }
+    }
+
+    fn build_recursive_helper(&mut self, current: &Derived<Package, SemVer>) {
+        match (&*current.cause1, &*current.cause2) {
+            (DerivationTree::External(e1), DerivationTree::External(e2)) => {
+                let line = format!(
+                    "Because {}, {}.",
+                    self.user.externals(e1, e2),
+                    self.user.terms(&current.terms)
+                );
+                self.lines.push(line);
+            }
+            (DerivationTree::Derived(derived), DerivationTree::External(external))
+            | (DerivationTree::External(external), DerivationTree::Derived(derived)) => {
+                self.report_one_each(derived, external, &current.terms)
+            }
+            (DerivationTree::Derived(d1), DerivationTree::Derived(d2)) => {
+                match (
+                    self.line_ref_of(d1.shared_id),
+                    self.line_ref_of(d2.shared_id),
+                ) {
+                    (Some(r1), Some(r2)) => {
+                        let line = format!(
+                            "Because {} ({}) and {} ({}), {}.",
+                            self.user.terms(&d1.terms),
+                            r1,
+                            self.user.terms(&d2.terms),
+                            r2,
+                            self.user.terms(&current.terms)
+                        );
+                        self.lines.push(line);
+                    }
+                    (Some(r1), None) => {
+                        self.build_recursive(d2);
+                        self.and_explain_ref(r1, d1, &current.terms);
+                    }
+                    (None, Some(r2)) => {
+                        self.build_recursive(d1);
+                        self.and_explain_ref(r2, d2, &current.terms);
+                    }
+                    (None, None) => {
+                        self.build_recursive(d1);
+                        if d1.shared_id.is_some() {
+                            self.lines.push(String::new());
+                            self.build_recursive(current);
+                        } else {
+                            self.add_line_ref();
+                            let r1 = self.ref_count;
+                            self.lines.push(String::new());
+                            self.build_recursive(d2);
+                            self.and_explain_ref(r1, d1, &current.terms);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn report_one_each(
+        &mut self,
+        derived: &Derived<Package, SemVer>,
+        external: &External<Package, SemVer>,
+        terms: &Map<Package, Term<SemVer>>,
+    ) {
+        match self.line_ref_of(derived.shared_id) {
+            Some(r) => {
+                let line = format!(
+                    "Because {} ({}) and {}, {}.",
+                    self.user.terms(&derived.terms),
+                    r,
+                    self.user.external(external),
+                    self.user.terms(terms)
+                );
+                self.lines.push(line);
+            }
+            None => match (&*derived.cause1, &*derived.cause2) {
+                (DerivationTree::Derived(prior), DerivationTree::External(prior_external))
+                | (DerivationTree::External(prior_external), DerivationTree::Derived(prior)) => {
+                    self.build_recursive(prior);
+                    let line = format!(
+                        "And because {}, {}.",
+                        self.user.externals(prior_external, external),
+                        self.user.terms(terms)
+                    );
+                    self.lines.push(line);
+                }
+                _ => {
+                    self.build_recursive(derived);
+                    let line = format!(
+                        "And because {}, {}.",
+                        self.user.external(external),
+                        self.user.terms(terms)
+                    );
+                    self.lines.push(line);
+                }
+            },
+        }
+    }
+
+    fn and_explain_ref(
+        &mut self,
+        r: usize,
+        derived: &Derived<Package, SemVer>,
+        terms: &Map<Package, Term<SemVer>>,
+    ) {
+        let line = format!(
+            "And because {} ({}), {}.",
+            self.user.terms(&derived.terms),
+            r,
+            self.user.terms(terms)
+        );
+        self.lines.push(line);
+    }
+
+    fn add_line_ref(&mut self) {
+        self.ref_count += 1;
+        if let Some(line) = self.lines.last_mut() {
+            line.push_str(&format!(" ({})", self.ref_count));
+        }
+    }
+
+    fn line_ref_of(&self, shared_id: Option<usize>) -> Option<usize> {
+        shared_id.and_then(|id| self.shared_with_ref.get(&id).copied())
+    }
+}
+
+impl UserTerms {
+    pub(crate) fn new(derivation_tree: &DerivationTree<Package, SemVer>) -> Self {
+        let mut user = Self {
+            proxy_ranges: Map::default(),
+            proxy_packages: Map::default(),
+        };
+        user.collect_proxies(derivation_tree);
+        user
+    }
+
+    /// Translation completed with the dependencies behind the proxies of the index.
+    pub(crate) fn for_index(
+        index: &Index,
+        derivation_tree: &DerivationTree<Package, SemVer>,
+    ) -> Self {
+        let mut user = Self::new(derivation_tree);
+        user.collect_index_proxies(index, derivation_tree);
+        user
+    }
+
+    fn collect_proxies(&mut self, tree: &DerivationTree<Package, SemVer>) {
+        match tree {
+            DerivationTree::External(External::FromDependencyOf(
+                proxy @ Package::Proxy { .. },
+                _,
+                dep,
+                range,
+            )) => {
+                let union = match self.proxy_ranges.get(proxy) {
+                    Some(known) => known.union(range),
+                    None => range.clone(),
+                };
+                self.proxy_ranges.insert(proxy.clone(), union);
+                if let Package::Bucket(bucket) | Package::Feature { base: bucket, .. } = dep {
+                    self.proxy_packages
+                        .insert(proxy.clone(), bucket.name.clone());
+                }
+            }
+            DerivationTree::External(_) => {}
+            DerivationTree::Derived(derived) => {
+                self.collect_proxies(&derived.cause1);
+                self.collect_proxies(&derived.cause2);
+            }
+        }
+    }
+
+    /// Complete the proxies of a derivation tree with the dependency behind them in the index,
+    /// for those whose own dependencies are not in the tree, like proxies without versions.
+    fn collect_index_proxies(&mut self, index: &Index, tree: &DerivationTree<Package, SemVer>) {
+        let packages: Vec<&Package> = match tree {
+            DerivationTree::External(External::FromDependencyOf(package, _, dep, _)) => {
+                vec![package, dep]
+            }
+            DerivationTree::External(
+                External::NotRoot(package, _)
+                | External::NoVersions(package, _)
+                | External::UnavailableDependencies(package, _),
+            ) => vec![package],
+            DerivationTree::Derived(derived) => {
+                self.collect_index_proxies(index, &derived.cause1);
+                self.collect_index_proxies(index, &derived.cause2);
+                derived.terms.keys().collect()
+            }
+        };
+        for proxy in packages {
+            if let Package::Proxy {
+                source,
+                optional,
+                target,
+                ..
+            } = proxy
+            {
+                if let Some(dep) = index.proxied_dep(source, optional.as_deref(), target) {
+                    self.proxy_packages
+                        .entry(proxy.clone())
+                        .or_insert_with(|| dep.package.clone());
+                    self.proxy_ranges
+                        .entry(proxy.clone())
+                        .or_insert_with(|| dep.range.clone());
+                }
+            }
+        }
+    }
+
+    /// Package behind a proxy, if known.
+    pub(crate) fn proxy_package(&self, proxy: &Package) -> Option<&PackageName> {
+        self.proxy_packages.get(proxy)
+    }
+
+    /// Explanation of two external incompatibilities, stated once if they read the same,
+    /// like the two halves of a dependency of the workspace root through a proxy.
+    pub(crate) fn externals(
+        &self,
+        e1: &External<Package, SemVer>,
+        e2: &External<Package, SemVer>,
+    ) -> String {
+        let (e1, e2) = (self.external(e1), self.external(e2));
+        if e1 == e2 {
+            e1
+        } else {
+            format!("{} and {}", e1, e2)
+        }
+    }
+
+    /// Explanation of an external incompatibility.
+    pub(crate) fn external(&self, external: &External<Package, SemVer>) -> String {
+        match external {
+            External::NotRoot(package, _) if is_workspace_root(package) => {
+                "we are solving the requirements".into()
+            }
+            External::NotRoot(package, version) => format!(
+                "we are solving dependencies of {}",
+                self.describe(package, &Range::exact(*version))
+            ),
+            External::NoVersions(Package::Singleton(name), _) => {
+                format!("no more versions of {} can be selected", name)
+            }
+            External::NoVersions(Package::Links(links), _) => {
+                format!("no more packages linking {} can be selected", links)
+            }
+            External::NoVersions(Package::SlotOf(Bucket { name, .. }), _)
+            | External::NoVersions(Package::Slot { name, .. }, _) => {
+                format!("no slot of {} is free", name)
+            }
+            External::NoVersions(package, range) => {
+                format!("there is no version of {}", self.describe(package, range))
+            }
+            External::UnavailableDependencies(package, range) => format!(
+                "dependencies of {} are unavailable",
+                self.describe(package, range)
+            ),
+            External::FromDependencyOf(package, range, dep, dep_range) => {
+                self.dependency(package, range, dep, dep_range)
+            }
+        }
+    }
+
+    /// Explanation of the terms of an incompatibility.
+    /// The workspace root is always selected, so it is left out of the terms.
+    pub(crate) fn terms(&self, terms: &Map<Package, Term<SemVer>>) -> String {
+        let mut terms: Vec<_> = terms.iter().collect();
+        if let [(package, Term::Positive(range)), (dep, Term::Negative(dep_range))]
+        | [(dep, Term::Negative(dep_range)), (package, Term::Positive(range))] = terms.as_slice()
+        {
+            return self.dependency(package, range, dep, dep_range);
+        }
+        terms.retain(|(package, term)| {
+            !(matches!(package, Package::Bucket(_))
+                && is_workspace_root(package)
+                && matches!(term, Term::Positive(_)))
+        });
+        match terms.as_slice() {
+            [] => "version solving failed".into(),
+            [(package, Term::Positive(range))] => {
+                format!("{} is forbidden", self.describe(package, range))
+            }
+            [(package, Term::Negative(range))] => {
+                format!("{} is mandatory", self.describe(package, range))
+            }
+            terms => {
+                let terms: Vec<_> = terms
+                    .iter()
+                    .map(|(package, term)| match term {
+                        Term::Positive(range) => self.describe(package, range),
+                        Term::Negative(range) => format!("not {}", self.describe(package, range)),
+                    })
+                    .collect();
+                terms.join(", ") + " are incompatible"
+            }
+        }
+    }
+
+    /// Explanation of a dependency, where a proxy stands for the package depending through it,
+    /// and a uniqueness marker for the limit it enforces.
+    fn dependency(
+        &self,
+        package: &Package,
+        range: &Range<SemVer>,
+        dep: &Package,
+        dep_range: &Range<SemVer>,
+    ) -> String {
+        let dependent = match package {
+            Package::Proxy { source, .. } => {
+                let source_bucket = Package::Bucket(source.0.clone());
+                self.describe(&source_bucket, &Range::exact(source.1))
+            }
+            _ => self.describe(package, range),
+        };
+        if is_workspace_root(package) {
+            return format!("the requirements ask for {}", self.describe(dep, dep_range));
+        }
+        match dep {
+            Package::Singleton(name) => {
+                format!("{} excludes other versions of {}", dependent, name)
+            }
+            Package::Links(links) => {
+                format!("{} excludes other packages linking {}", dependent, links)
+            }
+            Package::SlotOf(bucket) => format!(
+                "{} takes one of the limited slots of {}",
+                dependent, bucket.name
+            ),
+            _ => format!("{} depends on {}", dependent, self.describe(dep, dep_range)),
+        }
+    }
+
+    /// A package and a range of its versions, in user terms.
+    pub(crate) fn describe(&self, package: &Package, range: &Range<SemVer>) -> String {
+        match package {
+            Package::Bucket(bucket) => versions(&bucket.name, &self.range(package, range)),
+            Package::Feature { base, feature } => format!(
+                "{} with feature {}",
+                versions(&base.name, &self.range(package, range)),
+                feature
+            ),
+            Package::Proxy {
+                target, feature, ..
+            } => {
+                // Requirements of the workspace root are aliased by their position,
+                // and renamed dependencies by their local name.
+                let alias = if is_workspace_root(package) {
+                    requirement_package(target)
+                } else {
+                    target
+                };
+                let name = match self.proxy_packages.get(package) {
+                    Some(name) => name.to_string(),
+                    None => alias.to_string(),
+                };
+                let mut described = versions(&name, &self.range(package, range));
+                if let Some(feature) = feature {
+                    described = format!("{} with feature {}", described, feature);
+                }
+                if name != alias {
+                    described = format!("{} (as {})", described, alias);
+                }
+                described
+            }
+            Package::Singleton(name) => format!("the only version of {}", name),
+            Package::Links(links) => format!("the only package linking {}", links),
+            Package::SlotOf(bucket) => format!("a slot of {}", bucket.name),
+            Package::Slot { name, slot } => format!("slot {} of {}", slot, name),
+        }
+    }
+
+    /// Range of versions meant by a term on a package,
+    /// where any version of a bucket is the bucket range,
+    /// and any version of a proxy is the range of the dependency behind it.
+    pub(crate) fn range(&self, package: &Package, range: &Range<SemVer>) -> Range<SemVer> {
+        if range != &Range::any() {
+            return range.clone();
+        }
+        match package {
+            Package::Bucket(bucket) | Package::Feature { base: bucket, .. } => bucket.range(),
+            Package::Proxy { .. } => self
+                .proxy_ranges
+                .get(package)
+                .cloned()
+                .unwrap_or_else(Range::any),
+            _ => Range::any(),
+        }
+    }
+}
+
+/// A package name with a range of its versions, omitted if any version matches.
+fn versions(name: impl std::fmt::Display, range: &Range<SemVer>) -> String {
+    if range == &Range::any() {
+        name.to_string()
+    } else {
+        format!("{} {}", name, range)
+    }
+}
+
+/// Replace each derivation going through a proxy by the dependency it stands for:
+/// "a depends on the proxy" and "the proxy depends on d" become "a depends on d".
+pub(crate) fn collapse_proxies(
+    tree: DerivationTree<Package, SemVer>,
+) -> DerivationTree<Package, SemVer> {
+    match tree {
+        DerivationTree::External(_) => tree,
+        DerivationTree::Derived(mut derived) => {
+            derived.cause1 = Box::new(collapse_proxies(*derived.cause1));
+            derived.cause2 = Box::new(collapse_proxies(*derived.cause2));
+            if let (DerivationTree::External(e1), DerivationTree::External(e2)) =
+                (&*derived.cause1, &*derived.cause2)
+            {
+                if let Some(dependency) = skip_proxy(e1, e2).or_else(|| skip_proxy(e2, e1)) {
+                    return DerivationTree::External(dependency);
+                }
+            }
+            DerivationTree::Derived(derived)
+        }
+    }
+}
+
+/// The dependency of a package through a proxy, without the proxy.
+fn skip_proxy(
+    to_proxy: &External<Package, SemVer>,
+    from_proxy: &External<Package, SemVer>,
+) -> Option<External<Package, SemVer>> {
+    match (to_proxy, from_proxy) {
+        (
+            External::FromDependencyOf(package, range, proxy @ Package::Proxy { .. }, _),
+            External::FromDependencyOf(source, _, dep, dep_range),
+        ) if proxy == source => Some(External::FromDependencyOf(
+            package.clone(),
+            range.clone(),
+            dep.clone(),
+            dep_range.clone(),
+        )),
+        _ => None,
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::index::{Dep, Index};
+    use pubgrub::error::PubGrubError;
+    use std::str::FromStr;
+
+    #[test]
+    fn proxies_are_reported_as_dependencies() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(3, 0, 0), &["alpha"])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (3, 0, 0)..(4, 0, 0), &[])]);
+        index.add_feature::<core::ops::RangeFull>("d", (1, 0, 0), "alpha", &[]);
+        index.add_feature::<core::ops::RangeFull>("d", (2, 0, 0), "alpha", &[]);
+        index.add_deps::<core::ops::RangeFull>("d", (3, 0, 0), &[]);
+        index.add_singleton("d");
+        let root = Package::from_str("a#1").unwrap();
+        match pubgrub::solver::resolve(&index, root, (1, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => {
+                let report = UserReporter::report(&tree);
+                assert!(report.contains("b 1.0.0 depends on d "));
+                assert!(report.contains("with feature alpha"));
+                assert!(!report.contains("->"));
+                assert!(!report.contains('#'));
+            }
+            _ => panic!("expected no solution"),
+        }
+    }
+
+    #[test]
+    fn renamed_dependencies_are_reported_by_package() {
+        let mut index = Index::new();
+        index.add_renamed_deps(
+            "a",
+            (1, 0, 0),
+            &[("foo_v1", "foo", (1, 0, 0)..(2, 0, 0), &[])],
+        );
+        index.add_deps::<core::ops::RangeFull>("foo", (3, 0, 0), &[]);
+        let root = Package::from_str("a#1").unwrap();
+        match pubgrub::solver::resolve(&index, root, (1, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => assert_eq!(
+                index.report(&tree),
+                "a 1.0.0 depends on foo 1.0.0 <= v < 2.0.0 (as foo_v1)"
+            ),
+            _ => panic!("expected no solution"),
+        }
+    }
+
+    #[test]
+    fn workspace_root_is_the_requirements() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (2, 0, 0)..(3, 0, 0), &["beta"])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &["alpha"])]);
+        index.add_feature::<core::ops::RangeFull>("d", (1, 0, 0), "alpha", &[]);
+        index.add_deps::<core::ops::RangeFull>("e", (1, 0, 0), &[]);
+        let requirements = [Dep {
+            package: "a".into(),
+            range: Range::any(),
+            features: Default::default(),
+        }];
+        let (index, root) = index.with_workspace_root(requirements);
+        match pubgrub::solver::resolve(&index, root, (0, 0, 0)) {
+            Err(PubGrubError::NoSolution(tree)) => {
+                let report = index.report(&tree);
+                assert!(!report.contains(crate::workspace::WORKSPACE_ROOT));
+                assert!(report.contains("the requirements ask for a 1.0.0 <= v < 2.0.0"));
+                // The two halves of the dependency through the root proxy read the same.
+                assert!(report
+                    .lines()
+                    .all(|line| line.matches("the requirements ask for").count() <= 1));
+                assert!(report.ends_with(", version solving failed."));
+            }
+            _ => panic!("expected no solution"),
+        }
+    }
+
+    #[test]
+    fn proxy_hops_are_collapsed() {
+        let a = Package::from_str("a#1").unwrap();
+        let proxy = Package::Proxy {
+            source: (
+                Bucket {
+                    name: "a".into(),
+                    bucket: Some(1),
+                },
+                (1, 0, 0).into(),
+            ),
+            optional: None,
+            target: "d".into(),
+            feature: Some("alpha".into()),
+        };
+        let d2_alpha = Package::from_str("d#2/alpha").unwrap();
+        let mut terms = Map::default();
+        terms.insert(a.clone(), Term::Positive(Range::exact((1, 0, 0))));
+        terms.insert(
+            d2_alpha.clone(),
+            Term::Negative(Range::between((2, 0, 0), (3, 0, 0))),
+        );
+        let hop = DerivationTree::Derived(Derived {
+            terms,
+            shared_id: None,
+            cause1: Box::new(DerivationTree::External(External::FromDependencyOf(
+                a.clone(),
+                Range::exact((1, 0, 0)),
+                proxy.clone(),
+                Range::any(),
+            ))),
+            cause2: Box::new(DerivationTree::External(External::FromDependencyOf(
+                proxy,
+                Range::exact((2, 0, 0)),
+                d2_alpha,
+                Range::between((2, 0, 0), (3, 0, 0)),
+            ))),
+        });
+        let tree = DerivationTree::Derived(Derived {
+            terms: Map::default(),
+            shared_id: None,
+            cause1: Box::new(DerivationTree::External(External::NotRoot(
+                a,
+                (1, 0, 0).into(),
+            ))),
+            cause2: Box::new(hop),
+        });
+        let report = UserReporter::report(&tree);
+        assert_eq!(report.lines().count(), 1);
+        assert!(report.starts_with("Because we are solving dependencies of a 1.0.0 and "));
+        assert!(report.contains("a 1.0.0 depends on d "));
+        assert!(report.ends_with(" with feature alpha, version solving failed."));
+    }
+}
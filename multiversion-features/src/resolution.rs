@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{Feature, PackageName};
+use crate::lock::Lock;
+use crate::multiversion_optional_deps::Package;
+use core::fmt::Display;
+use pubgrub::type_aliases::SelectedDependencies;
+use pubgrub::version::SemanticVersion as SemVer;
+use std::collections::{BTreeMap as Map, BTreeSet as Set};
+
+/// A solution in terms of the packages of the index:
+/// the selected versions of each package with their enabled features,
+/// without the proxies and markers used to encode them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Resolution {
+    /// Enabled features of each selected version, per package.
+    pub packages: Map<PackageName, Map<SemVer, Set<Feature>>>,
+}
+
+impl Resolution {
+    /// Empty new resolution.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select a package version, with the given features.
+    pub fn add(
+        &mut self,
+        package: impl Into<PackageName>,
+        version: (u32, u32, u32),
+        features: &[&str],
+    ) {
+        self.packages
+            .entry(package.into())
+            .or_default()
+            .entry(version.into())
+            .or_default()
+            .extend(features.iter().map(|f| f.to_string()));
+    }
+
+    /// Decode the packages selected in a solution.
+    pub fn from_solution(solution: &SelectedDependencies<Package, SemVer>) -> Self {
+        let mut resolution = Self::new();
+        for (package, version) in solution {
+            let (base, feature) = match package {
+                Package::Bucket(base) => (base, None),
+                Package::Feature { base, feature } => (base, Some(feature.clone())),
+                _ => continue,
+            };
+            resolution
+                .packages
+                .entry(base.name.clone())
+                .or_default()
+                .entry(*version)
+                .or_default()
+                .extend(feature);
+        }
+        resolution
+    }
+
+    /// Enabled features of a selected package version.
+    pub fn features(&self, package: &PackageName, version: &SemVer) -> Option<&Set<Feature>> {
+        self.packages.get(package)?.get(version)
+    }
+
+    /// Packages selected in more than one version.
+    pub fn duplicates(&self) -> impl Iterator<Item = &PackageName> {
+        self.packages
+            .iter()
+            .filter(|(_, versions)| versions.len() > 1)
+            .map(|(name, _)| name)
+    }
+
+    /// Lock of the selected versions.
+    pub fn lock(&self) -> Lock {
+        let mut lock = Lock::new();
+        for (name, versions) in &self.packages {
+            lock.packages
+                .insert(name.clone(), versions.keys().copied().collect());
+        }
+        lock
+    }
+}
+
+impl Display for Resolution {
+    /// One line per selected version, like "d v1.0.0 [alpha, beta]" with its enabled features.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, versions) in &self.packages {
+            for (version, features) in versions {
+                write!(f, "{} v{}", name, version)?;
+                if !features.is_empty() {
+                    let features: Vec<&str> = features.iter().map(|f| f.as_str()).collect();
+                    write!(f, " [{}]", features.join(", "))?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::index::Index;
+    use std::str::FromStr;
+
+    #[test]
+    fn decode_solution() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(3, 0, 0), &["x"])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_feature::<core::ops::RangeFull>("b", (1, 0, 0), "x", &[]);
+        index.add_feature::<core::ops::RangeFull>("b", (2, 0, 0), "x", &[]);
+        let root = Package::from_str("a#1").unwrap();
+        let solution = pubgrub::solver::resolve(&index, root, (1, 0, 0)).unwrap();
+        let resolution = Resolution::from_solution(&solution);
+        let mut expected = Resolution::new();
+        expected.add("a", (1, 0, 0), &[]);
+        expected.add("b", (1, 0, 0), &[]);
+        expected.add("b", (2, 0, 0), &["x"]);
+        expected.add("c", (1, 0, 0), &[]);
+        assert_eq!(resolution, expected);
+        assert_eq!(
+            resolution.duplicates().collect::<Vec<_>>(),
+            vec![&"b".into()]
+        );
+        assert_eq!(resolution.lock(), Lock::from_solution(&solution));
+    }
+}
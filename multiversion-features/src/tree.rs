@@ -0,0 +1,321 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{Index, PackageName};
+use crate::multiversion_optional_deps::Package;
+use crate::workspace::WORKSPACE_ROOT;
+use pubgrub::type_aliases::SelectedDependencies;
+use pubgrub::version::SemanticVersion as SemVer;
+use std::collections::{BTreeMap as Map, BTreeSet as Set};
+use std::fmt::Write;
+
+/// A selected version of a package.
+type Node<'s> = (&'s PackageName, SemVer);
+
+/// Selected package versions of a solution, with their enabled features,
+/// and the dependencies between them, seeing through proxies.
+struct ResolvedGraph<'s> {
+    features: Map<Node<'s>, Set<&'s str>>,
+    dependencies: Map<Node<'s>, Set<Node<'s>>>,
+}
+
+impl Index {
+    /// Indented tree of a solution from the root, like `cargo tree`.
+    ///
+    /// Each line is a package version, like "d v1.0.0 [alpha, beta]" with its enabled features.
+    /// Dependencies are sorted, and a package version whose dependencies were already printed
+    /// is marked with "(*)" instead of being expanded again.
+    /// From the workspace root, there is one tree per requirement.
+    pub fn tree(&self, solution: &SelectedDependencies<Package, SemVer>, root: &Package) -> String {
+        let graph = ResolvedGraph::new(self, solution);
+        let root = match root {
+            Package::Bucket(base) | Package::Feature { base, .. } => solution
+                .get(root)
+                .and_then(|v| graph.features.get_key_value(&(&base.name, *v))),
+            _ => None,
+        };
+        let root = match root {
+            None => return String::new(),
+            Some((root, _)) => *root,
+        };
+        let roots = if is_workspace_root_node(root) {
+            graph.dependencies[&root].iter().copied().collect()
+        } else {
+            vec![root]
+        };
+        let mut printed = Set::new();
+        let mut trees = Vec::new();
+        for root in roots {
+            let mut tree = String::new();
+            graph.write_tree(&graph.dependencies, root, &mut printed, &mut tree);
+            trees.push(tree);
+        }
+        trees.join("\n")
+    }
+
+    /// Inverted trees of a solution, showing the dependents of each selected version
+    /// of a package, like `cargo tree -i`.
+    pub fn inverted_tree(
+        &self,
+        solution: &SelectedDependencies<Package, SemVer>,
+        package: &PackageName,
+    ) -> String {
+        let graph = ResolvedGraph::new(self, solution);
+        let dependents = graph.dependents();
+        let mut trees = Vec::new();
+        for node in graph.features.keys().filter(|(name, _)| *name == package) {
+            let mut tree = String::new();
+            graph.write_tree(&dependents, *node, &mut Set::new(), &mut tree);
+            trees.push(tree);
+        }
+        trees.join("\n")
+    }
+
+    /// Inverted trees of every package selected in more than one version,
+    /// like `cargo tree --duplicates`.
+    pub fn duplicates_tree(&self, solution: &SelectedDependencies<Package, SemVer>) -> String {
+        let graph = ResolvedGraph::new(self, solution);
+        let dependents = graph.dependents();
+        let mut trees = Vec::new();
+        let mut names: Vec<&PackageName> = graph.features.keys().map(|(name, _)| *name).collect();
+        names.dedup();
+        for name in names {
+            let versions: Vec<_> = graph.features.keys().filter(|(n, _)| *n == name).collect();
+            if versions.len() > 1 {
+                for node in versions {
+                    let mut tree = String::new();
+                    graph.write_tree(&dependents, *node, &mut Set::new(), &mut tree);
+                    trees.push(tree);
+                }
+            }
+        }
+        trees.join("\n")
+    }
+}
+
+impl<'s> ResolvedGraph<'s> {
+    fn new(index: &Index, solution: &'s SelectedDependencies<Package, SemVer>) -> Self {
+        let mut graph = ResolvedGraph {
+            features: Map::new(),
+            dependencies: Map::new(),
+        };
+        for (package, version) in solution {
+            let (base, feature) = match package {
+                Package::Bucket(base) => (base, None),
+                Package::Feature { base, feature } => (base, Some(feature.as_str())),
+                _ => continue,
+            };
+            let node = (&base.name, *version);
+            graph.features.entry(node).or_default().extend(feature);
+            let dependencies = graph.dependencies.entry(node).or_default();
+            for hop in index.selected_hops(solution, package, *version) {
+                let (dep, _) = solution.get_key_value(&hop.package).unwrap();
+                let dep = match dep {
+                    Package::Bucket(dep) | Package::Feature { base: dep, .. } => {
+                        (&dep.name, hop.version)
+                    }
+                    _ => continue,
+                };
+                // Features enable their own package version.
+                if dep != node {
+                    dependencies.insert(dep);
+                }
+            }
+        }
+        graph
+    }
+
+    /// The dependencies, inverted, leaving out the workspace root.
+    fn dependents(&self) -> Map<Node<'s>, Set<Node<'s>>> {
+        let mut dependents: Map<Node, Set<Node>> = Map::new();
+        for (node, dependencies) in &self.dependencies {
+            if is_workspace_root_node(*node) {
+                continue;
+            }
+            dependents.entry(*node).or_default();
+            for dep in dependencies {
+                dependents.entry(*dep).or_default().insert(*node);
+            }
+        }
+        dependents
+    }
+
+    /// Write a node, and its children if it was not printed yet.
+    fn write_tree(
+        &self,
+        children: &Map<Node<'s>, Set<Node<'s>>>,
+        node: Node<'s>,
+        printed: &mut Set<Node<'s>>,
+        tree: &mut String,
+    ) {
+        if self.write_line(children, node, printed, tree) {
+            self.write_children(children, node, "", printed, tree);
+        }
+    }
+
+    fn write_children(
+        &self,
+        children: &Map<Node<'s>, Set<Node<'s>>>,
+        node: Node<'s>,
+        prefix: &str,
+        printed: &mut Set<Node<'s>>,
+        tree: &mut String,
+    ) {
+        let count = children.get(&node).map_or(0, |c| c.len());
+        for (i, child) in children.get(&node).into_iter().flatten().enumerate() {
+            let last = i + 1 == count;
+            tree.push_str(prefix);
+            tree.push_str(if last { "└── " } else { "├── " });
+            if self.write_line(children, *child, printed, tree) {
+                let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                self.write_children(children, *child, &prefix, printed, tree);
+            }
+        }
+    }
+
+    /// Write the line of a node, returning whether its children should be written.
+    fn write_line(
+        &self,
+        children: &Map<Node<'s>, Set<Node<'s>>>,
+        node: Node<'s>,
+        printed: &mut Set<Node<'s>>,
+        tree: &mut String,
+    ) -> bool {
+        let (name, version) = node;
+        let _ = write!(tree, "{} v{}", name, version);
+        let features = &self.features[&node];
+        if !features.is_empty() {
+            let features: Vec<&str> = features.iter().copied().collect();
+            let _ = write!(tree, " [{}]", features.join(", "));
+        }
+        let expand = printed.insert(node);
+        if !expand && children.get(&node).is_some_and(|c| !c.is_empty()) {
+            tree.push_str(" (*)");
+        }
+        tree.push('\n');
+        expand
+    }
+}
+
+/// Whether a node is the synthetic root of a workspace.
+fn is_workspace_root_node((name, _): Node) -> bool {
+    name.name == WORKSPACE_ROOT
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::index::Dep;
+    use pubgrub::range::Range;
+    use std::str::FromStr;
+    type R = core::ops::RangeFull;
+
+    fn index() -> Index {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &["alpha"])]);
+        index.add_deps("b", (1, 0, 0), &[("e", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (2, 0, 0)..(4, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("e", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_feature("d", (1, 0, 0), "alpha", &[("f", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps::<R>("d", (2, 0, 0), &[]);
+        index.add_deps("e", (1, 0, 0), &[("f", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps::<R>("f", (1, 0, 0), &[]);
+        index
+    }
+
+    fn solution(index: &Index) -> SelectedDependencies<Package, SemVer> {
+        pubgrub::solver::resolve(index, Package::from_str("a#1").unwrap(), (1, 0, 0)).unwrap()
+    }
+
+    #[test]
+    fn tree_from_the_root() {
+        let index = index();
+        let tree = index.tree(&solution(&index), &Package::from_str("a#1").unwrap());
+        assert_eq!(
+            tree,
+            "\
+a v1.0.0
+├── b v1.0.0
+│   ├── d v1.0.0 [alpha]
+│   │   └── f v1.0.0
+│   └── e v1.0.0
+│       └── f v1.0.0
+└── c v1.0.0
+    ├── d v2.0.0
+    └── e v1.0.0 (*)
+"
+        );
+    }
+
+    #[test]
+    fn tree_per_requirement() {
+        let (index, root) = index().with_workspace_root([
+            Dep {
+                package: "b".into(),
+                range: Range::any(),
+                features: Default::default(),
+            },
+            Dep {
+                package: "d".into(),
+                range: Range::between((2, 0, 0), (3, 0, 0)),
+                features: Default::default(),
+            },
+        ]);
+        let solution = pubgrub::solver::resolve(&index, root.clone(), (0, 0, 0)).unwrap();
+        assert_eq!(
+            index.tree(&solution, &root),
+            "\
+b v1.0.0
+├── d v1.0.0 [alpha]
+│   └── f v1.0.0
+└── e v1.0.0
+    └── f v1.0.0
+
+d v2.0.0
+"
+        );
+        assert_eq!(
+            index.inverted_tree(&solution, &"d".into()),
+            "\
+d v1.0.0 [alpha]
+└── b v1.0.0
+
+d v2.0.0
+"
+        );
+    }
+
+    #[test]
+    fn inverted_and_duplicates() {
+        let index = index();
+        let solution = solution(&index);
+        assert_eq!(
+            index.inverted_tree(&solution, &"f".into()),
+            "\
+f v1.0.0
+├── d v1.0.0 [alpha]
+│   └── b v1.0.0
+│       └── a v1.0.0
+└── e v1.0.0
+    ├── b v1.0.0 (*)
+    └── c v1.0.0
+        └── a v1.0.0
+"
+        );
+        assert_eq!(
+            index.duplicates_tree(&solution),
+            "\
+d v1.0.0 [alpha]
+└── b v1.0.0
+    └── a v1.0.0
+
+d v2.0.0
+└── c v1.0.0
+    └── a v1.0.0
+"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{Index, PackageName, Policy};
+use crate::lock::Lock;
+use crate::multiversion_optional_deps::Package;
+use core::fmt::Display;
+use pubgrub::error::PubGrubError;
+use pubgrub::type_aliases::SelectedDependencies;
+use pubgrub::version::SemanticVersion as SemVer;
+use std::collections::BTreeSet as Set;
+
+/// A version change of a package between a lock and a new solution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A package version that was not locked is selected.
+    Added(PackageName, SemVer),
+    /// A locked package version is not selected anymore.
+    Removed(PackageName, SemVer),
+    /// A locked package version is replaced by another one.
+    Updated {
+        package: PackageName,
+        from: SemVer,
+        to: SemVer,
+    },
+}
+
+/// A solution after a targeted update, with the new lock and the changes from the previous one.
+pub struct UpdatedSolution {
+    /// The selected packages.
+    pub solution: SelectedDependencies<Package, SemVer>,
+    /// Lock of the new solution.
+    pub lock: Lock,
+    /// Version changes compared to the previous lock.
+    pub changes: Vec<Change>,
+}
+
+impl Index {
+    /// Update a single package against the lock of the index, like `cargo update -p`.
+    ///
+    /// Every bucket of the package is unlocked, and with `transitive`,
+    /// so are the locked packages it depends on, directly or not.
+    /// Other locked packages are pinned to their locked versions in the buckets they occupy,
+    /// so if the update requires changing them, it fails with a report naming them,
+    /// while it may still add buckets with new majors of them.
+    #[allow(clippy::result_large_err)]
+    pub fn update(
+        &self,
+        package: Package,
+        version: impl Into<SemVer>,
+        target: impl Into<PackageName>,
+        transitive: bool,
+    ) -> Result<UpdatedSolution, PubGrubError<Package, SemVer>> {
+        let target = target.into();
+        let unlocked = if transitive {
+            self.locked_dependencies(&target)
+        } else {
+            Set::from([target])
+        };
+        let mut index = self.clone();
+        index
+            .lock
+            .packages
+            .retain(|name, _| !unlocked.contains(name));
+        for (name, locked) in &index.lock.packages {
+            // Only the buckets with a locked version are pinned, so new majors stay free.
+            let single = index.policy(name) == Policy::Single;
+            if let Some(versions) = index.packages.get_mut(name) {
+                versions.retain(|v, _| {
+                    locked.contains(v)
+                        || !single && locked.iter().all(|locked| major(locked) != major(v))
+                });
+            }
+        }
+        let solution = pubgrub::solver::resolve(&index, package, version)?;
+        let lock = Lock::from_solution(&solution);
+        let changes = changes(&self.lock, &lock);
+        Ok(UpdatedSolution {
+            solution,
+            lock,
+            changes,
+        })
+    }
+
+    /// A locked package, and the locked packages its locked versions depend on, recursively.
+    fn locked_dependencies(&self, package: &PackageName) -> Set<PackageName> {
+        let mut visited = Set::from([package.clone()]);
+        let mut stack = vec![package.clone()];
+        while let Some(name) = stack.pop() {
+            let locked = self.lock.packages.get(&name).into_iter().flatten();
+            let versions = self.packages.get(&name);
+            for deps in locked.filter_map(|v| versions.and_then(|versions| versions.get(v))) {
+                let optional = deps.optional.values().flat_map(|deps| deps.values());
+                for dep in deps.mandatory.values().chain(optional) {
+                    if self.lock.packages.contains_key(&dep.package)
+                        && visited.insert(dep.package.clone())
+                    {
+                        stack.push(dep.package.clone());
+                    }
+                }
+            }
+        }
+        visited
+    }
+}
+
+/// Version changes between two locks.
+/// With multiple buckets of a package, old and new versions are paired
+/// by bucket first, and then in version order.
+pub fn changes(old: &Lock, new: &Lock) -> Vec<Change> {
+    let no_versions = Set::new();
+    let names: Set<&PackageName> = old.packages.keys().chain(new.packages.keys()).collect();
+    let mut changes = Vec::new();
+    for name in names {
+        let old_versions = old.packages.get(name).unwrap_or(&no_versions);
+        let new_versions = new.packages.get(name).unwrap_or(&no_versions);
+        let mut removed: Vec<SemVer> = old_versions.difference(new_versions).copied().collect();
+        let mut added: Vec<SemVer> = new_versions.difference(old_versions).copied().collect();
+        removed.retain(|from| {
+            let same_bucket = added.iter().position(|to| major(to) == major(from));
+            match same_bucket {
+                Some(i) => {
+                    let to = added.remove(i);
+                    changes.push(Change::Updated {
+                        package: name.clone(),
+                        from: *from,
+                        to,
+                    });
+                    false
+                }
+                None => true,
+            }
+        });
+        let paired = removed.len().min(added.len());
+        for (from, to) in removed.drain(..paired).zip(added.drain(..paired)) {
+            changes.push(Change::Updated {
+                package: name.clone(),
+                from,
+                to,
+            });
+        }
+        changes.extend(
+            removed
+                .into_iter()
+                .map(|v| Change::Removed(name.clone(), v)),
+        );
+        changes.extend(added.into_iter().map(|v| Change::Added(name.clone(), v)));
+    }
+    changes
+}
+
+fn major(version: &SemVer) -> u32 {
+    let (major, _, _) = (*version).into();
+    major
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Added(package, version) => write!(f, "Adding {} v{}", package, version),
+            Change::Removed(package, version) => write!(f, "Removing {} v{}", package, version),
+            Change::Updated { package, from, to } => {
+                write!(f, "Updating {} v{} -> v{}", package, from, to)
+            }
+        }
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::str::FromStr;
+    type R = core::ops::RangeFull;
+
+    fn root() -> Package {
+        Package::from_str("a#1").unwrap()
+    }
+
+    /// Index resolved and locked, before newer releases of b, c and d.
+    fn locked_index() -> Index {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (2, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps::<R>("d", (1, 0, 0), &[]);
+        index.add_deps::<R>("d", (2, 0, 0), &[]);
+        let solution = pubgrub::solver::resolve(&index, root(), (1, 0, 0)).unwrap();
+        index.lock = Lock::from_solution(&solution);
+        index.add_deps("b", (1, 1, 0), &[("d", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("c", (1, 1, 0), &[("d", (2, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps::<R>("d", (1, 1, 0), &[]);
+        index.add_deps::<R>("d", (2, 1, 0), &[]);
+        index
+    }
+
+    #[test]
+    fn other_packages_stay_locked() {
+        let mut index = locked_index();
+        let updated = index.update(root(), (1, 0, 0), "d", false).unwrap();
+        let locked: Set<SemVer> = Set::from([(1, 0, 0).into()]);
+        assert_eq!(updated.lock.packages[&"b".into()], locked);
+        assert_eq!(updated.lock.packages[&"c".into()], locked);
+
+        // a now requires a newer d, which cannot move while updating c.
+        index.add_deps("a", (1, 0, 0), &[("d", (1, 1, 0)..(2, 0, 0), &[])]);
+        match index.update(root(), (1, 0, 0), "c", false) {
+            Err(PubGrubError::NoSolution(tree)) => assert!(index.report(&tree).contains(
+                "Note: d is locked to 1.0.0, 2.0.0, which must move to match 1.1.0 <= v < 2.0.0"
+            )),
+            _ => panic!("expected the lock of d to block the update"),
+        }
+    }
+
+    #[test]
+    fn new_major_of_a_locked_package() {
+        let mut index = locked_index();
+        index.add_deps("b", (1, 2, 0), &[("d", (3, 0, 0)..(4, 0, 0), &[])]);
+        index.add_deps::<R>("d", (3, 0, 0), &[]);
+        let updated = index.update(root(), (1, 0, 0), "b", false).unwrap();
+        let locked: Set<SemVer> = Set::from([(2, 0, 0).into(), (3, 0, 0).into()]);
+        assert_eq!(updated.lock.packages[&"d".into()], locked);
+        assert_eq!(
+            updated.lock.packages[&"c".into()],
+            Set::from([(1, 0, 0).into()])
+        );
+    }
+
+    #[test]
+    fn update_every_bucket_of_a_package() {
+        let updated = locked_index()
+            .update(root(), (1, 0, 0), "d", false)
+            .unwrap();
+        assert_eq!(
+            updated.changes,
+            vec![
+                Change::Updated {
+                    package: "d".into(),
+                    from: (1, 0, 0).into(),
+                    to: (1, 1, 0).into(),
+                },
+                Change::Updated {
+                    package: "d".into(),
+                    from: (2, 0, 0).into(),
+                    to: (2, 1, 0).into(),
+                },
+            ]
+        );
+        assert_eq!(
+            updated.changes[0].to_string(),
+            "Updating d v1.0.0 -> v1.1.0"
+        );
+    }
+
+    #[test]
+    fn update_transitive_dependencies() {
+        let index = locked_index();
+        let updated = index.update(root(), (1, 0, 0), "b", false).unwrap();
+        assert_eq!(updated.changes.len(), 1);
+        let updated = index.update(root(), (1, 0, 0), "b", true).unwrap();
+        let changes: Vec<String> = updated
+            .changes
+            .iter()
+            .map(|change| change.to_string())
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                "Updating b v1.0.0 -> v1.1.0",
+                "Updating d v1.0.0 -> v1.1.0",
+                "Updating d v2.0.0 -> v2.1.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn changes_between_locks() {
+        let mut old = Lock::new();
+        old.add("d", (1, 0, 0));
+        old.add("e", (1, 0, 0));
+        let mut new = Lock::new();
+        new.add("d", (2, 0, 0));
+        new.add("d", (3, 0, 0));
+        new.add("f", (1, 0, 0));
+        let changes: Vec<String> = changes(&old, &new).iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            changes,
+            vec![
+                "Updating d v1.0.0 -> v2.0.0",
+                "Adding d v3.0.0",
+                "Removing e v1.0.0",
+                "Adding f v1.0.0",
+            ]
+        );
+    }
+}
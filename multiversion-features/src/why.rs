@@ -0,0 +1,414 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::Index;
+use crate::multiversion_optional_deps::Package;
+use crate::workspace::is_workspace_root;
+use core::fmt::Display;
+use pubgrub::range::Range;
+use pubgrub::solver::{Dependencies, DependencyProvider};
+use pubgrub::type_aliases::{Map, SelectedDependencies};
+use pubgrub::version::SemanticVersion as SemVer;
+
+/// A dependency path in a solution, from the root to a selected package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyPath {
+    /// The root package and its selected version.
+    pub root: (Package, SemVer),
+    /// Each following package, depended upon by the previous one.
+    pub hops: Vec<Hop>,
+}
+
+/// A selected package, with the range requested by its dependent in a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hop {
+    pub package: Package,
+    pub version: SemVer,
+    pub range: Range<SemVer>,
+}
+
+/// A chain of features in a solution, explaining why the last one is enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureChain {
+    /// The selected package requesting the first feature of the chain,
+    /// either the root or a package with a mandatory dependency on that feature.
+    pub requester: (Package, SemVer),
+    /// Whether the requester is the root of the resolution.
+    pub from_root: bool,
+    /// Features each requested by the previous one, ending with the explained feature.
+    pub features: Vec<(Package, SemVer)>,
+}
+
+impl Index {
+    /// All the dependency paths of a solution from the root to a selected package,
+    /// answering why that package, like "d#2" or "d#2/beta", is part of the solution.
+    ///
+    /// Proxies are seen through: a dependency going through a proxy is a single hop
+    /// to the package selected by the proxy, with the range of the dependency in the index.
+    /// Paths are sorted, and empty if the package is not selected.
+    pub fn why(
+        &self,
+        solution: &SelectedDependencies<Package, SemVer>,
+        root: &Package,
+        package: &Package,
+    ) -> Vec<DependencyPath> {
+        let version = match solution.get(root) {
+            Some(version) if solution.contains_key(package) => *version,
+            _ => return Vec::new(),
+        };
+        let mut paths = Vec::new();
+        let mut hops = Vec::new();
+        self.collect_paths(solution, root, version, package, &mut hops, &mut paths);
+        if !is_workspace_root(root) {
+            return paths
+                .into_iter()
+                .map(|hops| DependencyPath {
+                    root: (root.clone(), version),
+                    hops,
+                })
+                .collect();
+        }
+        // From the workspace root, paths start at the requirement they go through.
+        paths
+            .into_iter()
+            .filter(|hops| !hops.is_empty())
+            .map(|mut hops| {
+                let requirement = hops.remove(0);
+                DependencyPath {
+                    root: (requirement.package, requirement.version),
+                    hops,
+                }
+            })
+            .collect()
+    }
+
+    /// All the chains of requests enabling a feature selected in a solution, like "d#2/alpha".
+    ///
+    /// A feature may be requested by a dependency of a selected package,
+    /// or by another feature, itself requested by something else.
+    /// Each chain goes back from the explained feature through the features requesting it,
+    /// until a package that is not a feature, or the root, which may be a feature itself.
+    /// Chains are sorted, and empty if the feature is not selected.
+    pub fn why_feature(
+        &self,
+        solution: &SelectedDependencies<Package, SemVer>,
+        root: &Package,
+        feature: &Package,
+    ) -> Vec<FeatureChain> {
+        let (feature, version) = match solution.get_key_value(feature) {
+            Some((feature, version)) if solution.contains_key(root) => (feature, *version),
+            _ => return Vec::new(),
+        };
+        let mut dependents: Map<&Package, Vec<(&Package, SemVer)>> = Map::default();
+        for (package, version) in solution {
+            if matches!(package, Package::Bucket(_) | Package::Feature { .. }) {
+                for hop in self.selected_hops(solution, package, *version) {
+                    let (dep, _) = solution.get_key_value(&hop.package).unwrap();
+                    dependents.entry(dep).or_default().push((package, *version));
+                }
+            }
+        }
+        let mut chains = Vec::new();
+        let mut chain = vec![(feature, version)];
+        collect_feature_chains(&dependents, root, &mut chain, &mut chains);
+        chains.sort_by_key(|chain| chain.to_string());
+        chains
+    }
+
+    /// Depth first search of the paths from a package to the target.
+    /// Hops visited so far are kept in `hops`, to skip cycles.
+    fn collect_paths(
+        &self,
+        solution: &SelectedDependencies<Package, SemVer>,
+        package: &Package,
+        version: SemVer,
+        target: &Package,
+        hops: &mut Vec<Hop>,
+        paths: &mut Vec<Vec<Hop>>,
+    ) {
+        if package == target {
+            paths.push(hops.clone());
+            return;
+        }
+        for hop in self.selected_hops(solution, package, version) {
+            if hops.iter().any(|h| h.package == hop.package) {
+                continue;
+            }
+            hops.push(hop.clone());
+            self.collect_paths(solution, &hop.package, hop.version, target, hops, paths);
+            hops.pop();
+        }
+    }
+
+    /// The selected dependencies of a selected package, seeing through proxies,
+    /// sorted by package.
+    pub(crate) fn selected_hops(
+        &self,
+        solution: &SelectedDependencies<Package, SemVer>,
+        package: &Package,
+        version: SemVer,
+    ) -> Vec<Hop> {
+        let mut hops = Vec::new();
+        for (dep, range) in self.selected_dependencies(solution, package, version) {
+            let dep_version = solution[&dep];
+            match &dep {
+                // The range requested through a proxy is the one of the dependency behind it,
+                // not the one narrowed to the selected bucket.
+                Package::Proxy {
+                    source,
+                    optional,
+                    target,
+                    ..
+                } => {
+                    let requested = self
+                        .proxied_dep(source, optional.as_deref(), target)
+                        .map(|d| d.range.clone());
+                    for mut hop in self.selected_hops(solution, &dep, dep_version) {
+                        if let Some(range) = &requested {
+                            hop.range = range.clone();
+                        }
+                        hops.push(hop);
+                    }
+                }
+                Package::Singleton(_)
+                | Package::Links(_)
+                | Package::SlotOf(_)
+                | Package::Slot { .. } => {}
+                _ => hops.push(Hop {
+                    package: dep,
+                    version: dep_version,
+                    range,
+                }),
+            }
+        }
+        hops.sort_by_key(|hop| hop.package.to_string());
+        hops
+    }
+
+    /// Dependencies of a selected package, restricted to the selected ones.
+    pub(crate) fn selected_dependencies(
+        &self,
+        solution: &SelectedDependencies<Package, SemVer>,
+        package: &Package,
+        version: SemVer,
+    ) -> Vec<(Package, Range<SemVer>)> {
+        match self.get_dependencies(package, &version) {
+            Ok(Dependencies::Known(deps)) => deps
+                .into_iter()
+                .filter(|(dep, range)| solution.get(dep).is_some_and(|v| range.contains(v)))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Depth first search of the chains of requests leading to the last feature of `chain`,
+/// with the chain being built backwards.
+fn collect_feature_chains<'s>(
+    dependents: &Map<&'s Package, Vec<(&'s Package, SemVer)>>,
+    root: &Package,
+    chain: &mut Vec<(&'s Package, SemVer)>,
+    chains: &mut Vec<FeatureChain>,
+) {
+    let (feature, _) = *chain
+        .last()
+        .expect("chains start with the explained feature");
+    for &(requester, version) in dependents.get(feature).into_iter().flatten() {
+        if chain.iter().any(|(p, _)| *p == requester) {
+            continue;
+        }
+        let from_root = requester == root;
+        if from_root || !matches!(requester, Package::Feature { .. }) {
+            chains.push(FeatureChain {
+                requester: (requester.clone(), version),
+                from_root,
+                features: chain
+                    .iter()
+                    .rev()
+                    .map(|(p, v)| ((*p).clone(), *v))
+                    .collect(),
+            });
+        } else {
+            chain.push((requester, version));
+            collect_feature_chains(dependents, root, chain, chains);
+            chain.pop();
+        }
+    }
+}
+
+impl Display for DependencyPath {
+    /// "a#1 1.0.0 -> b#1 1.0.0 (1.0.0 <= v < 2.0.0) -> d#2 2.1.0 (2.0.0 <= v < 3.0.0)"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.root.0, self.root.1)?;
+        for hop in &self.hops {
+            write!(f, " -> {} {} ({})", hop.package, hop.version, hop.range)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for FeatureChain {
+    /// "a#1 1.0.0 (root) -> b#1/feat 1.0.0 -> f#1/rec_feat 1.0.0",
+    /// or "(requirements) -> b#1/feat 1.0.0" from the workspace root.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if is_workspace_root(&self.requester.0) {
+            write!(f, "(requirements)")?;
+        } else {
+            write!(f, "{} {}", self.requester.0, self.requester.1)?;
+        }
+        if self.from_root && !is_workspace_root(&self.requester.0) {
+            write!(f, " (root)")?;
+        }
+        for (feature, version) in &self.features {
+            write!(f, " -> {} {}", feature, version)?;
+        }
+        Ok(())
+    }
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::index::Dep;
+    use std::str::FromStr;
+    type R = core::ops::RangeFull;
+
+    fn why(index: &Index, package: &str) -> Vec<String> {
+        let root = Package::from_str("a#1").unwrap();
+        let solution = pubgrub::solver::resolve(index, root.clone(), (1, 0, 0)).unwrap();
+        index
+            .why(&solution, &root, &Package::from_str(package).unwrap())
+            .iter()
+            .map(|path| path.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn paths_to_a_bucket() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (2, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (1, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps::<R>("d", (1, 0, 0), &[]);
+        index.add_deps::<R>("d", (2, 0, 0), &[]);
+        assert_eq!(
+            why(&index, "d#2"),
+            vec![
+                "a#1 1.0.0 -> b#1 1.0.0 (1.0.0 <= v < 2.0.0) -> d#2 2.0.0 (2.0.0 <= v < 3.0.0)",
+                "a#1 1.0.0 -> c#1 1.0.0 (1.0.0 <= v < 2.0.0) -> d#2 2.0.0 (1.0.0 <= v < 3.0.0)",
+            ]
+        );
+        assert!(why(&index, "d#1").is_empty());
+    }
+
+    #[test]
+    fn paths_from_the_requirements() {
+        let mut index = Index::new();
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_feature::<R>("d", (1, 0, 0), "beta", &[]);
+        let (index, root) = index.with_workspace_root([
+            Dep {
+                package: "b".into(),
+                range: Range::any(),
+                features: Default::default(),
+            },
+            Dep {
+                package: "d".into(),
+                range: Range::any(),
+                features: ["beta".to_string()].into(),
+            },
+        ]);
+        let solution = pubgrub::solver::resolve(&index, root.clone(), (0, 0, 0)).unwrap();
+        let paths: Vec<String> = index
+            .why(&solution, &root, &Package::from_str("d#1").unwrap())
+            .iter()
+            .map(|path| path.to_string())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "b#1 1.0.0 -> d#1 1.0.0 (1.0.0 <= v < 2.0.0)",
+                "d#1/beta 1.0.0 -> d#1 1.0.0 (1.0.0)",
+            ]
+        );
+        let chains: Vec<String> = index
+            .why_feature(&solution, &root, &Package::from_str("d#1/beta").unwrap())
+            .iter()
+            .map(|chain| chain.to_string())
+            .collect();
+        assert_eq!(chains, vec!["(requirements) -> d#1/beta 1.0.0"]);
+    }
+
+    #[test]
+    fn paths_to_a_feature() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &["beta"])]);
+        index.add_feature::<R>("d", (1, 0, 0), "beta", &[]);
+        assert_eq!(
+            why(&index, "d#1/beta"),
+            vec!["a#1 1.0.0 -> b#1 1.0.0 (1.0.0 <= v < 2.0.0) -> d#1/beta 1.0.0 (1.0.0 <= v < 2.0.0)"]
+        );
+        assert_eq!(
+            why(&index, "d#1"),
+            vec!["a#1 1.0.0 -> b#1 1.0.0 (1.0.0 <= v < 2.0.0) -> d#1/beta 1.0.0 (1.0.0 <= v < 2.0.0) -> d#1 1.0.0 (1.0.0)"]
+        );
+    }
+
+    fn why_feature(index: &Index, root: &str, feature: &str) -> Vec<String> {
+        let root = Package::from_str(root).unwrap();
+        let solution = pubgrub::solver::resolve(index, root.clone(), (1, 0, 0)).unwrap();
+        index
+            .why_feature(&solution, &root, &Package::from_str(feature).unwrap())
+            .iter()
+            .map(|chain| chain.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn feature_requested_by_dependents_and_features() {
+        let mut index = Index::new();
+        index.add_deps("a", (1, 0, 0), &[("b", (1, 0, 0)..(2, 0, 0), &["feat"])]);
+        index.add_deps("a", (1, 0, 0), &[("c", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_feature(
+            "b",
+            (1, 0, 0),
+            "feat",
+            &[("d", (1, 0, 0)..(2, 0, 0), &["full"])],
+        );
+        index.add_deps("c", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &["alpha"])]);
+        index.add_feature::<R>("d", (1, 0, 0), "alpha", &[]);
+        index.add_feature(
+            "d",
+            (1, 0, 0),
+            "full",
+            &[("d", (1, 0, 0)..(2, 0, 0), &["alpha"])],
+        );
+        assert_eq!(
+            why_feature(&index, "a#1", "d#1/alpha"),
+            vec![
+                "a#1 1.0.0 (root) -> b#1/feat 1.0.0 -> d#1/full 1.0.0 -> d#1/alpha 1.0.0",
+                "c#1 1.0.0 -> d#1/alpha 1.0.0",
+            ]
+        );
+        assert!(why_feature(&index, "a#1", "d#1/beta").is_empty());
+    }
+
+    #[test]
+    fn feature_requested_at_the_root() {
+        let mut index = Index::new();
+        index.add_feature(
+            "a",
+            (1, 0, 0),
+            "feat",
+            &[("b", (1, 0, 0)..(2, 0, 0), &["x"])],
+        );
+        index.add_feature::<R>("b", (1, 0, 0), "x", &[]);
+        assert_eq!(
+            why_feature(&index, "a#1/feat", "b#1/x"),
+            vec!["a#1/feat 1.0.0 (root) -> b#1/x 1.0.0"]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::index::{range_from_bounds, Dep, Index, PackageName, Policy};
+use crate::multiversion_optional_deps::{Bucket, Package};
+use core::ops::RangeBounds;
+use pubgrub::error::PubGrubError;
+use pubgrub::type_aliases::SelectedDependencies;
+use pubgrub::version::SemanticVersion as SemVer;
+
+/// Name of the synthetic package depending on all the root requirements.
+/// No registry accepts "<" in package names, so it cannot clash with a real package.
+pub const WORKSPACE_ROOT: &str = "<workspace>";
+
+impl Index {
+    /// Resolve multiple root requirements at once, like the members of a workspace.
+    /// Each requirement is a package, a range and activated features.
+    /// A synthetic root package depending on all requirements is resolved,
+    /// and removed from the solution.
+    #[allow(clippy::result_large_err)]
+    pub fn resolve_workspace<P: Clone + Into<PackageName>, R: RangeBounds<(u32, u32, u32)>>(
+        &self,
+        requirements: &[(P, R, &[&str])],
+    ) -> Result<SelectedDependencies<Package, SemVer>, PubGrubError<Package, SemVer>> {
+        let requirements = requirements.iter().map(|(p, r, features)| Dep {
+            package: p.clone().into(),
+            range: range_from_bounds(r),
+            features: features.iter().map(|s| s.to_string()).collect(),
+        });
+        let (index, root) = self.with_workspace_root(requirements);
+        let mut solution = pubgrub::solver::resolve(&index, root, SemVer::zero())?;
+        remove_workspace_root(&mut solution);
+        Ok(solution)
+    }
+
+    /// Copy of the index with the synthetic root package depending on all requirements,
+    /// and that root package, to be resolved at version zero.
+    /// Requirements are renamed dependencies of the root, aliased by their position.
+    pub fn with_workspace_root(
+        &self,
+        requirements: impl IntoIterator<Item = Dep>,
+    ) -> (Index, Package) {
+        let root_name = PackageName::registry(WORKSPACE_ROOT);
+        let mut index = self.clone();
+        index.set_policy(root_name.clone(), Policy::Single);
+        let root_deps = index
+            .packages
+            .entry(root_name.clone())
+            .or_default()
+            .entry(SemVer::zero())
+            .or_default();
+        // Each requirement has its own alias, so that multiple requirements
+        // on the same package, like d@^1 and d@^2, are all kept.
+        for (position, dep) in requirements.into_iter().enumerate() {
+            root_deps
+                .mandatory
+                .insert(requirement_alias(&dep.package, position), dep);
+        }
+        let root = Package::Bucket(Bucket {
+            name: root_name,
+            bucket: None,
+        });
+        (index, root)
+    }
+}
+
+/// Alias of the requirement at a given position on a package, like "d[1]".
+fn requirement_alias(package: &PackageName, position: usize) -> String {
+    format!("{}[{}]", package, position)
+}
+
+/// Package of a requirement from its alias, so "d" for "d[1]".
+pub(crate) fn requirement_package(alias: &str) -> &str {
+    alias
+        .strip_suffix(']')
+        .and_then(|alias| alias.rsplit_once('['))
+        .map_or(alias, |(package, _)| package)
+}
+
+/// Whether a package is the synthetic root of a workspace, or a proxy of its dependencies.
+pub fn is_workspace_root(package: &Package) -> bool {
+    match package {
+        Package::Bucket(bucket) => bucket.name.name == WORKSPACE_ROOT,
+        Package::Proxy { source, .. } => source.0.name.name == WORKSPACE_ROOT,
+        _ => false,
+    }
+}
+
+/// Remove the synthetic root package of a workspace from a solution.
+pub fn remove_workspace_root(solution: &mut SelectedDependencies<Package, SemVer>) {
+    solution.retain(|package, _| !is_workspace_root(package));
+}
+
+// TESTS #######################################################################
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::str::FromStr;
+    type R = core::ops::RangeFull;
+
+    fn index() -> Index {
+        let mut index = Index::new();
+        index.add_deps("b", (1, 0, 0), &[("d", (1, 0, 0)..(2, 0, 0), &[])]);
+        index.add_deps("c", (1, 0, 0), &[("d", (2, 0, 0)..(3, 0, 0), &[])]);
+        index.add_deps::<R>("d", (1, 0, 0), &[]);
+        index.add_deps::<R>("d", (2, 0, 0), &[]);
+        index.add_feature("d", (2, 0, 0), "feat", &[("e", .., &[])]);
+        index.add_deps::<R>("e", (1, 0, 0), &[]);
+        index
+    }
+
+    #[test]
+    fn workspace_members_resolve_together() {
+        let solution = index()
+            .resolve_workspace(&[
+                ("b", (1, 0, 0)..(2, 0, 0), &[]),
+                ("c", (1, 0, 0)..(2, 0, 0), &[]),
+                ("d", (2, 0, 0)..(3, 0, 0), &["feat"]),
+            ])
+            .unwrap();
+        for package in ["b#1", "c#1", "d#1", "d#2", "d#2/feat", "e#1"] {
+            assert!(solution.contains_key(&Package::from_str(package).unwrap()));
+        }
+        assert!(solution
+            .keys()
+            .all(|p| !p.to_string().contains(WORKSPACE_ROOT)));
+    }
+
+    #[test]
+    fn requirements_on_the_same_package() {
+        let solution = index()
+            .resolve_workspace(&[
+                ("d", (1, 0, 0)..(2, 0, 0), &[]),
+                ("d", (2, 0, 0)..(3, 0, 0), &[]),
+            ])
+            .unwrap();
+        for package in ["d#1", "d#2"] {
+            assert!(solution.contains_key(&Package::from_str(package).unwrap()));
+        }
+
+        let mut index = index();
+        index.set_policy("d", Policy::Single);
+        let result = index.resolve_workspace(&[
+            ("d", (1, 0, 0)..(2, 0, 0), &[]),
+            ("d", (2, 0, 0)..(3, 0, 0), &[]),
+        ]);
+        assert!(matches!(result, Err(PubGrubError::NoSolution(_))));
+    }
+
+    #[test]
+    fn conflicting_requirements() {
+        let mut index = index();
+        index.set_policy("d", Policy::Single);
+        let result = index.resolve_workspace(&[("b", .., &[]), ("c", .., &[])]);
+        assert!(matches!(result, Err(PubGrubError::NoSolution(_))));
+    }
+}